@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+fn usage() -> ExitCode {
+    eprintln!(
+        "usage: sentinelai-daemon [--python-dir <dir>] [--control <socket path | 127.0.0.1:port>] [--db <file>] [--config <file>]"
+    );
+    ExitCode::from(2)
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut python_dir = None;
+    let mut control = None;
+    let mut db = None;
+    let mut config = None;
+
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--python-dir", Some(dir)) => python_dir = Some(PathBuf::from(dir)),
+            ("--control", Some(addr)) => control = Some(addr),
+            ("--db", Some(path)) => db = Some(PathBuf::from(path)),
+            ("--config", Some(path)) => config = Some(PathBuf::from(path)),
+            _ => return usage(),
+        }
+    }
+
+    let db = db.unwrap_or_else(|| PathBuf::from(sentinelai_lib::DB_FILE));
+
+    match sentinelai_lib::run_daemon(python_dir, control.as_deref(), &db, config.as_deref()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+fn usage() -> ExitCode {
+    eprintln!("usage: sentinelai-pcap <capture.pcap|capture.pcapng> [--models <dir>]");
+    ExitCode::from(2)
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut capture = None;
+    let mut models = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--models" => match args.next() {
+                Some(dir) => models = Some(PathBuf::from(dir)),
+                None => return usage(),
+            },
+            "-h" | "--help" => return usage(),
+            _ if capture.is_none() => capture = Some(PathBuf::from(arg)),
+            _ => return usage(),
+        }
+    }
+
+    let Some(capture) = capture else {
+        return usage();
+    };
+    let models = models.unwrap_or_else(sentinelai_lib::default_models_dir);
+
+    match sentinelai_lib::run_offline(&capture, &models) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
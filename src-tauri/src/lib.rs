@@ -1,24 +1,48 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod modules;
-use modules::helpers::{mask_ip, rigid_sorter};
-use modules::networks::collect_networks;
+use modules::alerts::Alert;
+use modules::capture::{capture_live, list_devices, CaptureOptions, LiveCapture};
+use modules::arp::ArpBinding;
+use modules::config::{apply_config, load_or_create, watch_config, Config, CONFIG_FILE};
+use modules::daemon::{default_control_addr, serve_control, StdoutNotifier};
+use modules::deltas::{start_publisher, NetworkSnapshot};
+use modules::detector::Detector;
+use modules::dns::DnsRecord;
+use modules::helpers::rigid_sorter;
+use modules::monitoring::{control_interface, resolve_state, MonitorAction, MonitoringState};
+use modules::networks::{collect_networks, flow_owner, scan_processes, CaptureDevice};
+use modules::notifier::Notifier;
+use modules::pipeline::{Pipeline, PipelineStats};
+use modules::offline::{analyze_pcap, check_capture_file, OfflineResult, OfflineVerdict};
+use modules::privacy::{policy_from_env, Privacy, Redact, SALT_FILE};
 use modules::python_runner::PythonRunner;
-use modules::structures::{AnomalyDetection, AppState, Bandwidth, IPInfo, NetworkInfo};
+use modules::storage::{start_retention, FlowRecord, HistoryQuery, InterfaceSnapshot, Store};
+use modules::structures::{
+    AnomalyDetection, AppState, Bandwidth, BandwidthSample, Connection, IPInfo, NetworkInfo,
+    ProcessInfo,
+};
+use modules::protocol::{Envelope, FlowTuple, ModelSource, Verdict, WorkerMessage};
+use modules::supervisor::{supervise, worker_health, Worker, WorkerHealth, WorkerOutput};
 
 use chrono::Utc;
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
-use std::process::Child;
+use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{thread, vec};
 use sysinfo::{Networks, ProcessesToUpdate, System};
 use tauri::Emitter;
-use tauri::Listener;
 use tauri::{AppHandle, Manager};
 
+pub use modules::storage::DB_FILE;
+
+/// The privacy policy every IP shown to the user goes through.
+fn current_privacy(state: &AppState) -> Privacy {
+    state.privacy.read().unwrap().clone()
+}
+
 #[tauri::command]
 async fn get_networks(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<NetworkInfo>, String> {
     let lock = state
@@ -26,87 +50,466 @@ async fn get_networks(state: tauri::State<'_, Arc<AppState>>) -> Result<Vec<Netw
         .read()
         .map_err(|_| "Failed to acquire lock".to_string())?;
 
+    Ok(lock.clone().redact(&current_privacy(&state)))
+}
+
+#[tauri::command]
+async fn get_worker_health(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<WorkerHealth>, String> {
+    Ok(worker_health(&state))
+}
+
+/// The interfaces as of the last `network_delta`, to resync after a gap in `seq`.
+#[tauri::command]
+async fn get_snapshot(state: tauri::State<'_, Arc<AppState>>) -> Result<NetworkSnapshot, String> {
+    Ok(state.network_changes.snapshot().redact(&current_privacy(&state)))
+}
+
+/// Queue depths and drop counters of the worker message pipeline.
+#[tauri::command]
+async fn get_pipeline_stats(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<PipelineStats, String> {
+    Ok(state.pipeline.stats())
+}
+
+/// Most recent alerts first, optionally for a single worker interface.
+#[tauri::command]
+async fn get_alerts(
+    iface: Option<String>,
+    limit: Option<usize>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<Alert>, String> {
+    let limit = limit.unwrap_or(modules::alerts::MAX_ALERTS);
+
+    Ok(state
+        .alerts
+        .list(iface.as_deref(), limit)
+        .redact(&current_privacy(&state)))
+}
+
+/// Most recent DNS messages first, optionally for a single worker interface.
+#[tauri::command]
+async fn get_dns_log(
+    iface: Option<String>,
+    limit: Option<usize>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<DnsRecord>, String> {
+    let limit = limit.unwrap_or(modules::dns::MAX_DNS_RECORDS);
+
+    Ok(state
+        .dns_log
+        .list(iface.as_deref(), limit)
+        .redact(&current_privacy(&state)))
+}
+
+/// IP→MAC bindings learned from ARP, optionally for a single worker interface.
+#[tauri::command]
+async fn get_arp_table(
+    iface: Option<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<ArpBinding>, String> {
+    Ok(state
+        .arp
+        .bindings(iface.as_deref())
+        .redact(&current_privacy(&state)))
+}
+
+/// Sockets listening on every interface (0.0.0.0 / ::).
+#[tauri::command]
+async fn get_wildcard_sockets(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<Connection>, String> {
+    let lock = state
+        .wildcard_sockets
+        .read()
+        .map_err(|_| "Failed to acquire lock".to_string())?;
+
+    Ok(lock.clone().redact(&current_privacy(&state)))
+}
+
+/// Processes holding sockets, with their connections, from the last monitor cycle.
+#[tauri::command]
+async fn get_processes(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<ProcessInfo>, String> {
+    let lock = state
+        .processes
+        .read()
+        .map_err(|_| "Failed to acquire lock".to_string())?;
+
+    Ok(lock.clone().redact(&current_privacy(&state)))
+}
+
+/// The running configuration, as saved in `config.toml`.
+#[tauri::command]
+async fn get_config(state: tauri::State<'_, Arc<AppState>>) -> Result<Config, String> {
+    let lock = state
+        .config
+        .read()
+        .map_err(|_| "Failed to acquire lock".to_string())?;
+
     Ok(lock.clone())
 }
 
-fn get_interfaces(dir: &Path, runner: &PythonRunner) -> HashMap<String, String> {
-    let script = dir.join("helper.py");
+/// Validates and saves `config`, then applies it without a restart.
+#[tauri::command]
+async fn set_config(config: Config, state: tauri::State<'_, Arc<AppState>>) -> Result<Config, String> {
+    let path = state
+        .config_path
+        .read()
+        .map_err(|_| "Failed to acquire lock".to_string())?
+        .clone()
+        .ok_or("Configuration file is unavailable")?;
+
+    config.save(&path).map_err(|e| e.to_string())?;
+
+    // Loading a new models directory takes a while
+    let state = Arc::clone(&state);
+    let applied = config.clone();
+    tauri::async_runtime::spawn_blocking(move || apply_config(&state, applied))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(config)
+}
+
+async fn control(
+    id: String,
+    action: MonitorAction,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<MonitoringState, String> {
+    let state = Arc::clone(&state);
+
+    // Saves the config file
+    tauri::async_runtime::spawn_blocking(move || control_interface(&state, &id, action))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Monitors an interface, overriding an exclude rule if needed.
+#[tauri::command]
+async fn start_interface(
+    id: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<MonitoringState, String> {
+    control(id, MonitorAction::Start, state).await
+}
+
+/// Stops the worker of an interface until it is started again.
+#[tauri::command]
+async fn stop_interface(
+    id: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<MonitoringState, String> {
+    control(id, MonitorAction::Stop, state).await
+}
+
+/// Keeps the worker running but ignores its verdicts.
+#[tauri::command]
+async fn pause_interface(
+    id: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<MonitoringState, String> {
+    control(id, MonitorAction::Pause, state).await
+}
+
+#[tauri::command]
+async fn resume_interface(
+    id: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<MonitoringState, String> {
+    control(id, MonitorAction::Resume, state).await
+}
+
+fn history_store(state: &AppState) -> Result<Arc<Store>, String> {
+    state
+        .store
+        .read()
+        .map_err(|_| "Failed to acquire lock".to_string())?
+        .clone()
+        .ok_or_else(|| "History storage is unavailable".to_string())
+}
+
+#[tauri::command]
+async fn query_alerts(
+    query: HistoryQuery,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<Alert>, String> {
+    let store = history_store(&state)?;
+    let privacy = current_privacy(&state);
+
+    tauri::async_runtime::spawn_blocking(move || store.query_alerts(&query))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|alerts| alerts.redact(&privacy))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn query_flows(
+    query: HistoryQuery,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<FlowRecord>, String> {
+    let store = history_store(&state)?;
+    let privacy = current_privacy(&state);
+
+    tauri::async_runtime::spawn_blocking(move || store.query_flows(&query))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|flows| flows.redact(&privacy))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn query_snapshots(
+    query: HistoryQuery,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<InterfaceSnapshot>, String> {
+    let store = history_store(&state)?;
+    let privacy = current_privacy(&state);
+
+    tauri::async_runtime::spawn_blocking(move || store.query_snapshots(&query))
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|snapshots| snapshots.redact(&privacy))
+        .map_err(|e| e.to_string())
+}
+
+/// Starts analysing a saved capture and returns its id right away. Verdicts
+/// follow as `offline_verdict` events, then one `offline_report` event ends it.
+#[tauri::command]
+async fn analyze_capture(
+    path: String,
+    app: AppHandle,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<u64, String> {
+    let detector = state
+        .detector
+        .read()
+        .map_err(|_| "Failed to acquire lock".to_string())?
+        .clone()
+        .ok_or("Detection models are still loading")?;
+    check_capture_file(Path::new(&path))?;
+
+    let privacy = current_privacy(&state);
+    let state = Arc::clone(&state);
+    let (id, cancel) = state.analyses.start();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = analyze_pcap(Path::new(&path), &detector, &cancel, |verdict| {
+            let verdict = verdict.clone().redact(&privacy);
+            let _ = app.emit("offline_verdict", OfflineVerdict { id, verdict });
+        });
+
+        state.analyses.finish(id);
+        let _ = app.emit("offline_report", OfflineResult::new(id, result));
+    });
+
+    Ok(id)
+}
+
+/// Stops analysis `id`. Its report says it was cancelled.
+#[tauri::command]
+async fn cancel_analysis(id: u64, state: tauri::State<'_, Arc<AppState>>) -> Result<(), String> {
+    match state.analyses.cancel(id) {
+        true => Ok(()),
+        false => Err(format!("No analysis {id} is running")),
+    }
+}
+
+/// Headless offline analysis for the `sentinelai-pcap` binary.
+/// Prints one protocol verdict per line on stdout, exactly as a live worker
+/// would send it, and a summary on stderr.
+pub fn run_offline(capture: &Path, models_dir: &Path) -> Result<(), String> {
+    let detector = Detector::load(models_dir, None);
+    let never = AtomicBool::new(false);
+
+    let report = analyze_pcap(capture, &detector, &never, |verdict| {
+        let envelope = Envelope::new(WorkerMessage::Verdict(verdict.clone()));
+        if let Ok(line) = serde_json::to_string(&envelope) {
+            println!("{line}");
+        }
+    })?;
+
+    eprintln!(
+        "{}: {} flows, {} anomalous",
+        report.file, report.flows, report.anomalies
+    );
+    for (label, count) in &report.labels {
+        eprintln!("  {label}: {count}");
+    }
+
+    Ok(())
+}
+
+fn exe_relative(path: &Path) -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(path)))
+        .filter(|dir| dir.is_dir())
+        .unwrap_or_else(|| path.to_path_buf())
+}
+
+/// `models/` next to the executable, falling back to the working directory.
+pub fn default_models_dir() -> PathBuf {
+    exe_relative(Path::new("models"))
+}
+
+/// The bundled interpreter, laid out like the Tauri resource directory.
+pub fn default_python_dir() -> PathBuf {
+    exe_relative(&Path::new("resources").join("embedded-python"))
+}
+
+/// `subsystem/` next to the executable, where `helper.py` is bundled.
+pub fn default_scripts_dir() -> PathBuf {
+    exe_relative(Path::new("subsystem"))
+}
+
+/// Loads the detection models off the calling thread. Live captures
+/// meter flows without classifying them until the models are in.
+fn load_detector(state: &Arc<AppState>, models_dir: PathBuf) {
+    let state = Arc::clone(state);
+    thread::spawn(move || {
+        let detector = Detector::load(&models_dir, None);
+
+        // Under the detector lock, so a config applied meanwhile isn't missed
+        let mut slot = state.detector.write().unwrap();
+        detector.set_lstm_threshold(state.config.read().unwrap().detection.lstm_threshold);
+        *slot = Some(Arc::new(detector));
+    });
+}
+
+fn get_interfaces(scripts_dir: &Path, runner: Option<&PythonRunner>) -> Vec<CaptureDevice> {
+    // helper.py also knows each device's MAC, libpcap alone is enough to capture
+    let Some(runner) = runner else {
+        return list_devices();
+    };
+    let script = scripts_dir.join("helper.py");
 
     let output = match runner.run(script) {
-        Some(out) => out,
-        None => {
-            return HashMap::new();
+        Ok(out) => out,
+        Err(e) => {
+            eprintln!("{e}");
+            return Vec::new();
         }
     };
 
-    let list = serde_json::from_str::<Vec<serde_json::Value>>(&output).unwrap_or_default();
+    serde_json::from_str::<Vec<CaptureDevice>>(&output)
+        .inspect_err(|e| eprintln!("Unexpected helper.py output: {e}"))
+        .unwrap_or_default()
+}
 
-    let mut map = HashMap::new();
+fn load_capture_devices(
+    scripts_dir: &Path,
+    runner: Option<&PythonRunner>,
+    retries: u32,
+) -> Vec<CaptureDevice> {
+    let mut devices = get_interfaces(scripts_dir, runner);
+
+    // Retry mechanism (`monitor.device_retries` times)
+    let mut retry = 0;
+    while devices.is_empty() && retry < retries {
+        println!("⚠ capture device list empty, retrying... ({retry})");
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        devices = get_interfaces(scripts_dir, runner);
+        retry += 1;
+    }
 
-    for item in list {
-        if let (Some(name), Some(ipv4)) = (item["name"].as_str(), item["ipv4"].as_str()) {
-            map.insert(ipv4.to_string(), name.to_string());
-        }
+    if devices.is_empty() {
+        println!("❌ ERROR: Could not list capture devices. Workers will not start.");
     }
 
-    map
+    devices
 }
 
+/// Starts the worker of one capture device: flows are metered and
+/// classified in-process, its messages go into `pipeline`.
 fn spawn_worker(
-    app_handle: &AppHandle,
+    pipeline: &Pipeline,
+    state: &Arc<AppState>,
     iface_name: &str,
-    dir: &std::path::Path,
-    runner: Arc<PythonRunner>,
-) -> Option<Child> {
-    let script: PathBuf = dir.join("worker.py");
-
-    let mut child = runner.run_args(script, iface_name)?;
-
-    if let Some(stdout) = child.stdout.take() {
-        let handle = app_handle.clone();
-        tauri::async_runtime::spawn_blocking(move || {
-            let reader = BufReader::new(stdout);
-            for line in reader.lines().flatten() {
-                let _ = handle.emit("get_anomaly", line);
-            }
-        });
-    }
+    options: &CaptureOptions,
+    output: Arc<WorkerOutput>,
+) -> Option<LiveCapture> {
+    let pipeline = pipeline.clone();
+    let state = Arc::clone(state);
+    let device = iface_name.to_string();
+
+    let options = options.clone();
+
+    LiveCapture::start(iface_name, move |stop| {
+        capture_live(&device, &options, &state, &pipeline, &output, stop)
+    })
+    .inspect_err(|e| eprintln!("Failed to start capture on {iface_name}: {e}"))
+    .ok()
+}
 
-    if let Some(stderr) = child.stderr.take() {
-        tauri::async_runtime::spawn_blocking(move || {
-            let reader = BufReader::new(stderr);
-            for line in reader.lines().flatten() {
-                eprintln!("stderr: {}", line);
-            }
-        });
-    }
+fn start_monitoring(notifier: Arc<dyn Notifier>, state: Arc<AppState>) {
+    // ---- Throttled network_delta events ----
+    start_publisher(Arc::clone(&notifier), Arc::clone(&state));
 
-    Some(child)
-}
+    // ---- Worker output: update state → notify ----
+    let pipeline = Pipeline::start(notifier, Arc::clone(&state));
+
+    // ---- Restart crashed workers with backoff ----
+    supervise(pipeline.clone(), Arc::clone(&state));
 
-fn start_monitoring(app: AppHandle, state: Arc<AppState>, dir: PathBuf, runner: Arc<PythonRunner>) {
     thread::spawn(move || {
         let mut previous: Vec<String> = vec![];
-        let mut first_emit = true;
 
         loop {
             // ----  Collect latest network info ----
-            let networks = collect_networks();
-
-            let current_ips: Vec<String> = networks
+            let devices = state.capture_devices.lock().unwrap().clone();
+            let scan = collect_networks(&state.rates, &devices);
+            let (networks, iface_map) = (scan.networks, scan.iface_map);
+            *state.wildcard_sockets.write().unwrap() = scan.wildcard_sockets;
+            *state.processes.write().unwrap() = scan.processes;
+            *state.local_addrs.write().unwrap() = scan.local_addrs;
+
+            // Key = interface identity (index/name/MAC), addressed or not
+            let current_ids: Vec<String> = networks.iter().map(|net| net.id.clone()).collect();
+
+            // ---- Publish interface → capture device map ----
+            *state.iface_map.lock().unwrap() = iface_map.clone();
+
+            // ---- Decide what to do with every interface ----
+            let config = state.config.read().unwrap().clone();
+            let monitoring: HashMap<String, MonitoringState> = networks
                 .iter()
-                .filter_map(|net| net.ip_info.ipv4.clone())
+                .map(|net| {
+                    let device = iface_map.get(&net.id);
+                    let mode =
+                        resolve_state(&config.monitor, &net.name, device.map(String::as_str));
+                    (net.id.clone(), mode)
+                })
                 .collect();
 
-            // ---- Read iface → name map ----
-            let iface_map = state.iface_map.lock().unwrap();
+            // ---- Start, pause and stop workers to match ----
+            {
+                let mut workers = state.workers.lock().unwrap();
 
-            // ---- Start workers for NEW interfaces ----
-            for ipv4 in &current_ips {
-                if !previous.contains(ipv4) {
-                    if let Some(iface_name) = iface_map.get(ipv4) {
-                        if let Some(child) = spawn_worker(&app, iface_name, &dir, runner.clone()) {
-                            state.workers.lock().unwrap().insert(ipv4.clone(), child);
+                for (id, mode) in &monitoring {
+                    match mode {
+                        MonitoringState::Monitoring | MonitoringState::Paused => {
+                            let paused = *mode == MonitoringState::Paused;
+                            if let Some(worker) = workers.get(id) {
+                                worker.set_paused(paused);
+                            } else if !paused {
+                                // A paused interface gets its worker back on resume
+                                if let Some(iface_name) = iface_map.get(id) {
+                                    let options = config.capture_options();
+                                    let worker =
+                                        Worker::start(iface_name, options, &pipeline, &state);
+                                    workers.insert(id.clone(), worker);
+                                }
+                            }
+                        }
+                        _ => {
+                            if let Some(mut worker) = workers.remove(id) {
+                                worker.stop();
+                            }
                         }
                     }
                 }
@@ -116,11 +519,10 @@ fn start_monitoring(app: AppHandle, state: Arc<AppState>, dir: PathBuf, runner:
             {
                 let mut workers = state.workers.lock().unwrap();
 
-                for old_ip in previous.iter() {
-                    if !current_ips.contains(old_ip) {
-                        if let Some(mut child) = workers.remove(old_ip) {
-                            let _ = child.kill();
-                            let _ = child.wait();
+                for old_id in previous.iter() {
+                    if !current_ids.contains(old_id) {
+                        if let Some(mut worker) = workers.remove(old_id) {
+                            worker.stop();
                         }
                     }
                 }
@@ -131,200 +533,332 @@ fn start_monitoring(app: AppHandle, state: Arc<AppState>, dir: PathBuf, runner:
                 let mut lock = state.networks.write().unwrap();
 
                 for new in &networks {
-                    // Key = IPv4 address
-                    let ipv4 = match &new.ip_info.ipv4 {
-                        Some(v) => v.clone(),
-                        None => continue,
-                    };
-
-                    if let Some(existing) = lock
-                        .iter_mut()
-                        .find(|n| n.ip_info.ipv4 == Some(ipv4.clone()))
-                    {
-                        // -------- Preserve anomaly state --------
-                        let anomaly = existing.anomaly.clone();
+                    if let Some(existing) = lock.iter_mut().find(|n| n.id == new.id) {
+                        // -------- Re-derive anomaly state so old alerts age out --------
+                        let anomaly = match iface_map.get(&new.id) {
+                            Some(iface_name) => state.alerts.status(iface_name),
+                            None => existing.anomaly.clone(),
+                        };
+                        let mode = monitoring.get(&new.id).copied().unwrap_or_default();
 
                         // -------- Update all live fields --------
-                        existing.id = existing.id.clone();
                         existing.name = new.name.clone();
                         existing.status = new.status.clone();
                         existing.ip_info = new.ip_info.clone();
                         existing.bandwidth = new.bandwidth.clone();
+                        existing.bandwidth_history = new.bandwidth_history.clone();
                         existing.received_bytes = new.received_bytes;
                         existing.transmitted_bytes = new.transmitted_bytes;
                         existing.active_ports = new.active_ports.clone();
+                        existing.connections = new.connections.clone();
                         existing.last_seen = new.last_seen;
                         existing.cpu_usage = new.cpu_usage;
+                        existing.monitoring_state = mode;
 
                         // -------- Restore anomaly detection --------
                         existing.anomaly = anomaly;
                     } else {
                         // -------- Add new network --------
-                        lock.push(new.clone());
+                        let mut new = new.clone();
+                        new.monitoring_state = monitoring.get(&new.id).copied().unwrap_or_default();
+                        lock.push(new);
                     }
                 }
 
                 // -------- Remove networks no longer present --------
-                let new_ipv4s: Vec<String> = networks
-                    .iter()
-                    .filter_map(|n| n.ip_info.ipv4.clone())
-                    .collect();
-
-                lock.retain(|n| {
-                    if let Some(ip) = &n.ip_info.ipv4 {
-                        new_ipv4s.contains(ip)
-                    } else {
-                        false
-                    }
-                });
+                lock.retain(|n| current_ids.contains(&n.id));
             }
 
-            // ---- Send frontend event ----
-            if first_emit {
-                let nets = state.networks.read().unwrap().clone();
-                let _ = app.emit("network_update", nets);
-                first_emit = false;
+            // ---- Persist interface snapshot ----
+            if let Some(store) = state.store.read().unwrap().clone() {
+                store
+                    .insert_snapshots(&state.networks.read().unwrap())
+                    .inspect_err(|e| eprintln!("{e}"))
+                    .ok();
             }
 
+            // ---- Publish what changed this cycle ----
+            state.network_changes.mark();
+
             // ---- update previous list for next loop ----
-            previous = current_ips.clone();
+            previous = current_ids.clone();
 
             // ---- Sleep for next cycle ----
-            thread::sleep(Duration::from_secs(10));
+            state
+                .monitor_wake
+                .wait(Duration::from_secs(config.monitor.interval_secs));
         }
     });
 }
 
-pub fn run() {
-    // ---- Create global application state ----
-    let app_state = Arc::new(AppState::new());
+const OWNER_RESCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The process behind `flow`: the last monitor cycle's sockets first, then a
+/// rescan, at most one per `OWNER_RESCAN_INTERVAL` however many anomalies miss.
+fn lookup_owner(state: &AppState, flow: &FlowTuple) -> Option<(u32, String)> {
+    let owner = flow_owner(
+        flow,
+        &state.processes.read().unwrap(),
+        &state.local_addrs.read().unwrap(),
+    );
+    if owner.is_some() {
+        return owner;
+    }
 
-    tauri::Builder::default()
-        .manage(app_state.clone())
-        .setup(move |app| {
-            // ---- Prepare Python directory path ----
-            let python_dir = app
-                .path()
-                .resource_dir()
-                .unwrap()
-                .join("resources")
-                .join("embedded-python");
+    {
+        let mut rescanned = state.owner_rescan.lock().unwrap();
+        if rescanned.is_some_and(|at| at.elapsed() < OWNER_RESCAN_INTERVAL) {
+            return None;
+        }
+        *rescanned = Some(Instant::now());
+    }
 
-            // ---- Python Runner ----
-            let runner = Arc::new(PythonRunner::new(&python_dir));
-
-            // ---- Load interface → NIC name map ----
-            let mut iface_map = get_interfaces(&python_dir, &runner);
-
-            // Retry mechanism (5 times)
-            let mut retry = 0;
-            while iface_map.is_empty() && retry < 5 {
-                println!("⚠ iface_map empty, retrying... ({retry})");
-                std::thread::sleep(std::time::Duration::from_millis(500));
-                iface_map = get_interfaces(&python_dir, &runner);
-                retry += 1;
-            }
+    let processes = scan_processes();
+    let owner = flow_owner(flow, &processes, &state.local_addrs.read().unwrap());
+    *state.processes.write().unwrap() = processes;
+    owner
+}
 
-            if iface_map.is_empty() {
-                println!("❌ ERROR: Could not load interface map. Workers will not start.");
-            }
+/// Applies the configured thresholds to one worker verdict, records it and
+/// re-derives the status of its interface from recent alerts.
+/// Interface changes are published through `state.network_changes`.
+fn handle_anomaly(state: &AppState, verdict: &mut Verdict) {
+    state.config.read().unwrap().detection.apply(verdict);
+
+    let verdict = &*verdict;
+    let iface_name = verdict.iface.as_str();
+    // Only anomalies are worth a socket-table lookup, and scans, beacons,
+    // tunnels and spoofing span many flows or none
+    let owner = match verdict.model {
+        Some(ModelSource::Scan | ModelSource::Beacon | ModelSource::Dns | ModelSource::Arp) => None,
+        _ if verdict.is_anomaly => lookup_owner(state, &verdict.flow),
+        _ => None,
+    };
+    let alert = state.alerts.record(verdict, owner);
+    let recorded = alert.is_some();
 
-            // ---- Save iface_map into AppState ----
-            {
-                let mut locked_map = app_state.iface_map.lock().unwrap();
-                *locked_map = iface_map;
+    // ---- Persist alert history, flows are batched by the pipeline ----
+    if let Some(store) = state.store.read().unwrap().clone() {
+        if let Some(alert) = &alert {
+            store.insert_alert(alert).inspect_err(|e| eprintln!("{e}")).ok();
+        }
+    }
+
+    // Reverse lookup: capture device → interface key
+    let id = {
+        let iface_map = state.iface_map.lock().unwrap();
+        iface_map.iter().find_map(|(id, name)| {
+            if name == iface_name {
+                Some(id.clone())
+            } else {
+                None
             }
+        })
+    };
 
-            // ---- Setup event listener for "get_anomaly" ----\
-            // let app_handle = app.handle().clone();
-            let app_handle = app.app_handle();
+    let id = match id {
+        Some(id) => id,
+        None => {
+            eprintln!("Could not map iface '{}' to a monitored interface", iface_name);
+            return;
+        }
+    };
 
-            let emit_handle = app_handle.clone();
+    let status = state.alerts.status(iface_name);
+    let mut changed = recorded;
 
-            let state_clone = Arc::clone(&app_state);
+    let mut networks = state.networks.write().unwrap();
 
-            app_handle.listen("get_anomaly", move |event| {
-                let payload = event.payload();
+    for net in networks.iter_mut() {
+        if net.id == id {
+            if net.anomaly.is_anomalous != status.is_anomalous
+                || net.anomaly.anomaly_type != status.anomaly_type
+            {
+                changed = true;
+            }
 
-                if payload.is_empty() {
-                    return;
-                }
+            net.anomaly = status.clone();
+            net.last_seen = Utc::now();
+        }
+    }
 
-                let raw = payload;
-                let first: serde_json::Value = serde_json::from_str(raw).unwrap();
-
-                // If the first layer is a string → parse inner
-                let msg: serde_json::Value = if first.is_string() {
-                    serde_json::from_str(first.as_str().unwrap()).unwrap()
-                } else {
-                    first
-                };
-
-                let iface_name = match msg.get("iface").and_then(|v| v.as_str()) {
-                    Some(x) => x,
-                    None => return,
-                };
-
-                let label = msg
-                    .get("label")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("unknown")
-                    .to_string();
-
-                let is_anomaly = msg
-                    .get("is_anomaly")
-                    .and_then(|v| v.as_bool())
-                    .unwrap_or(false);
-
-                let iface_raw = msg["iface"].as_str().unwrap_or("");
-
-                // Reverse lookup: device_name → ipv4
-                let iface_map = state_clone.iface_map.lock().unwrap();
-                let ipv4 = iface_map.iter().find_map(|(ip, name)| {
-                    if name == iface_raw {
-                        Some(ip.clone())
-                    } else {
-                        None
-                    }
-                });
+    if changed {
+        state.network_changes.mark();
+    }
+}
 
-                if ipv4.is_none() {
-                    eprintln!("Could not map iface '{}' to an IPv4", iface_raw);
-                    return;
-                }
+/// Headless monitoring for the `sentinelai-daemon` binary: same captures,
+/// models and anomaly handling as the app, reported on stdout and a local control socket.
+/// `python_dir`, `config_path` and `control_addr` default to the config's
+/// `paths.python_dir`, and `config.toml` and the control socket next to the
+/// database.
+pub fn run_daemon(
+    python_dir: Option<PathBuf>,
+    control_addr: Option<&str>,
+    db_path: &Path,
+    config_path: Option<&Path>,
+) -> Result<(), String> {
+    let state = Arc::new(AppState::new());
+
+    let config_path = config_path
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| db_path.with_file_name(CONFIG_FILE));
+    let config = load_or_create(&config_path);
+    let python_dir = python_dir
+        .or_else(|| config.paths.python_dir.clone())
+        .unwrap_or_else(default_python_dir);
+    let scripts_dir = config
+        .paths
+        .scripts_dir
+        .clone()
+        .unwrap_or_else(default_scripts_dir);
+    let models_dir = config
+        .paths
+        .models_dir
+        .clone()
+        .unwrap_or_else(default_models_dir);
+    *state.bundled_models_dir.write().unwrap() = default_models_dir();
+    let retries = config.monitor.device_retries;
+    let retention_days = config.retention_days();
+
+    *state.privacy.write().unwrap() = Privacy::load(
+        policy_from_env().unwrap_or(config.privacy.policy),
+        &db_path.with_file_name(SALT_FILE),
+    );
+    *state.config.write().unwrap() = config;
+    *state.config_path.write().unwrap() = Some(config_path.clone());
+    watch_config(Arc::clone(&state), config_path)
+        .inspect_err(|e| eprintln!("config: {e}. Edits need a restart."))
+        .ok();
+
+    let store = Arc::new(Store::open(db_path, retention_days).map_err(|e| e.to_string())?);
+    start_retention(Arc::clone(&store));
+    *state.store.write().unwrap() = Some(store);
+    load_detector(&state, models_dir);
+
+    let runner = PythonRunner::new(&python_dir)
+        .inspect_err(|e| eprintln!("{e}, listing devices with libpcap"))
+        .ok();
+
+    *state.capture_devices.lock().unwrap() =
+        load_capture_devices(&scripts_dir, runner.as_ref(), retries);
+
+    let notifier = Arc::new(StdoutNotifier::new(Arc::clone(&state)));
+    start_monitoring(notifier, Arc::clone(&state));
+
+    let control_addr = control_addr
+        .map(str::to_string)
+        .unwrap_or_else(|| default_control_addr(db_path));
+    serve_control(&control_addr, state)
+}
 
-                let ipv4 = ipv4.unwrap();
+pub fn run() {
+    // ---- Create global application state ----
+    let app_state = Arc::new(AppState::new());
 
-                {
-                    let mut networks = state_clone.networks.write().unwrap();
+    tauri::Builder::default()
+        .manage(app_state.clone())
+        .setup(move |app| {
+            // ---- Load configuration and watch it for edits ----
+            let config_path = app.path().app_config_dir().unwrap().join(CONFIG_FILE);
+            let config = load_or_create(&config_path);
+            let retries = config.monitor.device_retries;
+            let retention_days = config.retention_days();
+
+            // ---- Prepare Python interpreter and script paths ----
+            let python_dir = config.paths.python_dir.clone().unwrap_or_else(|| {
+                app.path()
+                    .resource_dir()
+                    .unwrap()
+                    .join("resources")
+                    .join("embedded-python")
+            });
+            let scripts_dir = config
+                .paths
+                .scripts_dir
+                .clone()
+                .unwrap_or_else(|| app.path().resource_dir().unwrap().join("subsystem"));
+
+            let data_dir = app.path().app_data_dir().unwrap();
+
+            // ---- Privacy policy and per-install salt ----
+            *app_state.privacy.write().unwrap() = Privacy::load(
+                policy_from_env().unwrap_or(config.privacy.policy),
+                &data_dir.join(SALT_FILE),
+            );
 
-                    for net in networks.iter_mut() {
-                        if net.ip_info.ipv4.as_ref() == Some(&ipv4) {
-                            net.id = iface_name.to_string();
-                            net.anomaly.is_anomalous = is_anomaly;
-                            net.anomaly.anomaly_type = Some(label.to_uppercase());
+            *app_state.bundled_models_dir.write().unwrap() =
+                app.path().resource_dir().unwrap().join("models");
+            *app_state.config.write().unwrap() = config;
+            *app_state.config_path.write().unwrap() = Some(config_path.clone());
+            watch_config(Arc::clone(&app_state), config_path)
+                .inspect_err(|e| eprintln!("config: {e}. Edits need a restart."))
+                .ok();
+
+            // ---- Load detection models in the background ----
+            let models_dir = app_state
+                .config
+                .read()
+                .unwrap()
+                .paths
+                .models_dir
+                .clone()
+                .unwrap_or_else(|| app_state.bundled_models_dir.read().unwrap().clone());
+            load_detector(&app_state, models_dir);
+
+            // ---- Open history database ----
+            let db_path = data_dir.join(DB_FILE);
+            match Store::open(&db_path, retention_days) {
+                Ok(store) => {
+                    let store = Arc::new(store);
+                    start_retention(Arc::clone(&store));
+                    *app_state.store.write().unwrap() = Some(store);
+                }
+                Err(e) => println!("❌ ERROR: {e}. History will not be saved."),
+            }
 
-                            net.last_seen = Utc::now();
-                        }
-                    }
+            // ---- Python Runner ----
+            let runner = match PythonRunner::new(&python_dir) {
+                Ok(runner) => Some(runner),
+                Err(e) => {
+                    println!("⚠ {e}, listing devices with libpcap");
+                    None
                 }
+            };
 
-                // Emit updated networks to frontend
-                let nets = state_clone.networks.read().unwrap().clone();
-                let _ = emit_handle.emit("network_update", nets);
-            });
+            // ---- Load capture devices (matched to interfaces every cycle) ----
+            let devices = load_capture_devices(&scripts_dir, runner.as_ref(), retries);
+            *app_state.capture_devices.lock().unwrap() = devices;
 
             // ---- Start continuous monitoring ----
-            start_monitoring(
-                app_handle.clone(),
-                Arc::clone(&app_state),
-                python_dir,
-                runner.clone(),
-            );
+            let app_handle = app.app_handle();
+
+            start_monitoring(Arc::new(app_handle.clone()), Arc::clone(&app_state));
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![get_networks])
+        .invoke_handler(tauri::generate_handler![
+            get_networks,
+            get_snapshot,
+            get_worker_health,
+            get_pipeline_stats,
+            get_alerts,
+            get_dns_log,
+            get_arp_table,
+            get_wildcard_sockets,
+            get_processes,
+            get_config,
+            set_config,
+            start_interface,
+            stop_interface,
+            pause_interface,
+            resume_interface,
+            query_alerts,
+            query_flows,
+            query_snapshots,
+            analyze_capture,
+            cancel_analysis
+        ])
         .run(tauri::generate_context!())
         .expect("Error while running Tauri application");
 }
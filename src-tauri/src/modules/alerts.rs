@@ -0,0 +1,134 @@
+use crate::modules::protocol::{ModelSource, Verdict};
+use crate::modules::tls::TlsInfo;
+use crate::{AnomalyDetection, DefaultStruct};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+// Oldest alerts are dropped once the log is full
+pub const MAX_ALERTS: usize = 1000;
+// An interface stays anomalous while it has an alert this recent
+pub const RECENT_WINDOW_SECS: i64 = 300;
+
+DefaultStruct! {
+    pub struct Alert {
+        id: u64,
+        iface: String,
+        src_ip: String,
+        src_port: u16,
+        dst_ip: String,
+        dst_port: u16,
+        protocol: u8,
+        label: String,
+        model: Option<ModelSource>,
+        score: f64,
+        flow_start: Option<DateTime<Utc>>,
+        detected_at: DateTime<Utc>,
+        received_at: DateTime<Utc>,
+        pid: Option<u32>,
+        process: Option<String>,
+        period_secs: Option<f64>,
+        domains: Vec<String>,
+        macs: Vec<String>,
+        tls: Option<TlsInfo>,
+    }
+}
+
+impl Alert {
+    fn from_verdict(id: u64, verdict: &Verdict, owner: Option<(u32, String)>) -> Self {
+        let (pid, process) = owner.unzip();
+
+        Self {
+            id,
+            iface: verdict.iface.clone(),
+            src_ip: verdict.flow.src_ip.clone(),
+            src_port: verdict.flow.src_port,
+            dst_ip: verdict.flow.dst_ip.clone(),
+            dst_port: verdict.flow.dst_port,
+            protocol: verdict.flow.protocol,
+            label: verdict.label.to_uppercase(),
+            model: verdict.model,
+            score: verdict.score,
+            flow_start: verdict.flow_start,
+            detected_at: verdict.timestamp,
+            received_at: Utc::now(),
+            pid,
+            process,
+            period_secs: verdict.period_secs,
+            domains: verdict.domains.clone(),
+            macs: verdict.macs.clone(),
+            tls: verdict.tls.as_deref().cloned(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct AlertLogInner {
+    alerts: VecDeque<Alert>,
+    next_id: u64,
+}
+
+/// Bounded, newest-last list of anomalous flows across all interfaces.
+#[derive(Debug, Default)]
+pub struct AlertLog {
+    inner: Mutex<AlertLogInner>,
+}
+
+impl AlertLog {
+    /// Records an anomalous verdict with its owning process, if known.
+    /// Benign verdicts are ignored.
+    pub fn record(&self, verdict: &Verdict, owner: Option<(u32, String)>) -> Option<Alert> {
+        if !verdict.is_anomaly {
+            return None;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.next_id += 1;
+
+        let alert = Alert::from_verdict(inner.next_id, verdict, owner);
+        if inner.alerts.len() == MAX_ALERTS {
+            inner.alerts.pop_front();
+        }
+        inner.alerts.push_back(alert.clone());
+
+        Some(alert)
+    }
+
+    /// Newest first, optionally limited to one interface.
+    pub fn list(&self, iface: Option<&str>, limit: usize) -> Vec<Alert> {
+        let inner = self.inner.lock().unwrap();
+
+        inner
+            .alerts
+            .iter()
+            .rev()
+            .filter(|a| iface.is_none_or(|name| a.iface == name))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// Interface status from its alerts inside the recent window:
+    /// anomalous if there are any, typed by the most recent one.
+    pub fn status(&self, iface: &str) -> AnomalyDetection {
+        let cutoff = Utc::now() - Duration::seconds(RECENT_WINDOW_SECS);
+        let inner = self.inner.lock().unwrap();
+
+        let latest = inner
+            .alerts
+            .iter()
+            .rev()
+            .take_while(|a| a.received_at >= cutoff)
+            .find(|a| a.iface == iface);
+
+        match latest {
+            Some(alert) => AnomalyDetection {
+                is_anomalous: true,
+                anomaly_type: Some(alert.label.clone()),
+            },
+            None => AnomalyDetection::new(),
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use crate::modules::config::ArpConfig;
+use crate::modules::protocol::{ArpPacket, FlowTuple, ModelSource, Verdict};
+use crate::DefaultStruct;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::net::Ipv4Addr;
+use std::sync::Mutex;
+
+// ---- ARP for IPv4 over Ethernet, the only kind a LAN carries ----
+const HTYPE_ETHERNET: u16 = 1;
+const PTYPE_IPV4: u16 = 0x0800;
+const OP_REPLY: u16 = 2;
+const ARP_LEN: usize = 28;
+
+// Bounds memory under a flood of spoofed senders
+const MAX_BINDINGS_PER_IFACE: usize = 4096;
+const MAX_GRATUITOUS: usize = 4096;
+const MAX_MACS_PER_ALERT: usize = 10;
+
+// Who has which IPv4 address on one interface's segment
+DefaultStruct! {
+    pub struct ArpBinding {
+        iface: String,
+        ip: String,
+        mac: String,
+        first_seen: DateTime<Utc>,
+        last_seen: DateTime<Utc>,
+        // Before the last change, if any
+        previous_mac: Option<String>,
+        changes: u32,
+        is_gateway: bool,
+    }
+}
+
+/// One decoded ARP message.
+#[derive(Debug, Clone)]
+pub struct ArpFrame {
+    pub iface: String,
+    pub timestamp: DateTime<Utc>,
+    pub is_reply: bool,
+    pub sender_mac: String,
+    pub sender_ip: Ipv4Addr,
+    pub target_mac: String,
+    pub target_ip: Ipv4Addr,
+    pub gateway: Option<Ipv4Addr>,
+}
+
+impl ArpFrame {
+    /// An unsolicited announcement of the sender's own binding.
+    fn is_gratuitous(&self) -> bool {
+        self.sender_ip == self.target_ip
+            || (self.is_reply && self.target_mac == "ff:ff:ff:ff:ff:ff")
+    }
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(at)?, *data.get(at + 1)?]))
+}
+
+fn mac(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn ipv4(bytes: &[u8]) -> Ipv4Addr {
+    Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])
+}
+
+/// Decodes a message a capture forwarded. `None` for anything but a
+/// well-formed Ethernet/IPv4 ARP message.
+pub fn decode(packet: &ArpPacket) -> Option<ArpFrame> {
+    let data = &packet.payload;
+    if data.len() < ARP_LEN
+        || u16_at(data, 0)? != HTYPE_ETHERNET
+        || u16_at(data, 2)? != PTYPE_IPV4
+        || data[4] != 6
+        || data[5] != 4
+    {
+        return None;
+    }
+
+    Some(ArpFrame {
+        iface: packet.iface.clone(),
+        timestamp: packet.timestamp,
+        is_reply: u16_at(data, 6)? == OP_REPLY,
+        sender_mac: mac(&data[8..14]),
+        sender_ip: ipv4(&data[14..18]),
+        target_mac: mac(&data[18..24]),
+        target_ip: ipv4(&data[24..28]),
+        gateway: packet.gateway,
+    })
+}
+
+// ---------------------------------------------------------------------
+// SPOOFING DETECTION
+// ---------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Signal {
+    // An address recently bound to one MAC claimed by another
+    Conflict,
+    // Too many unsolicited announcements on the segment
+    GratuitousFlood,
+    // The default gateway answers from a new MAC
+    GatewayChange,
+}
+
+impl Signal {
+    fn label(self) -> &'static str {
+        match self {
+            Signal::Conflict => "ARP_SPOOFING",
+            Signal::GratuitousFlood => "GRATUITOUS_ARP_FLOOD",
+            Signal::GatewayChange => "GATEWAY_MAC_CHANGED",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Segment {
+    bindings: HashMap<Ipv4Addr, ArpBinding>,
+    gateway: Option<Ipv4Addr>,
+    // Learned from the first message the gateway sends
+    gateway_mac: Option<String>,
+    // Gratuitous messages of the last `flood_window_secs`, with their sender
+    gratuitous: VecDeque<(DateTime<Utc>, String)>,
+    // (signal, address) → when it was last alerted, to alert once per window:
+    // `flood_window_secs` for floods, `conflict_window_secs` for the rest
+    alerted: HashMap<(Signal, Ipv4Addr), DateTime<Utc>>,
+}
+
+/// Per interface, learns IP→MAC bindings from every ARP message and the
+/// default gateway's MAC, and alerts when they are contested.
+#[derive(Debug, Default)]
+pub struct ArpMonitor {
+    segments: Mutex<HashMap<String, Segment>>,
+}
+
+impl ArpMonitor {
+    /// Feeds one decoded message. Returns an anomalous verdict per signal
+    /// it raised, with the MAC addresses involved.
+    pub fn observe(&self, frame: &ArpFrame, config: &ArpConfig) -> Vec<Verdict> {
+        let now = frame.timestamp;
+        let window = Duration::seconds(config.conflict_window_secs as i64);
+        let flood_window = Duration::seconds(config.flood_window_secs as i64);
+        let mut segments = self.segments.lock().unwrap();
+        let segment = segments.entry(frame.iface.clone()).or_default();
+
+        // ---- Gateway, re-learned when the route moves ----
+        if frame.gateway.is_some() && frame.gateway != segment.gateway {
+            segment.gateway = frame.gateway;
+            segment.gateway_mac = None;
+        }
+
+        let mut signals: Vec<(Signal, Vec<String>)> = Vec::new();
+
+        // ---- Gratuitous ARP rate ----
+        if frame.is_gratuitous() {
+            segment
+                .gratuitous
+                .push_back((now, frame.sender_mac.clone()));
+        }
+        while segment.gratuitous.len() > MAX_GRATUITOUS
+            || segment
+                .gratuitous
+                .front()
+                .is_some_and(|(at, _)| *at < now - flood_window)
+        {
+            segment.gratuitous.pop_front();
+        }
+        if frame.is_gratuitous() && segment.gratuitous.len() >= config.flood_threshold {
+            let mut macs: Vec<String> = Vec::new();
+            for (_, mac) in segment.gratuitous.iter().rev() {
+                if !macs.contains(mac) && macs.len() < MAX_MACS_PER_ALERT {
+                    macs.push(mac.clone());
+                }
+            }
+            signals.push((Signal::GratuitousFlood, macs));
+        }
+
+        // ---- Binding of the sender, probes from 0.0.0.0 claim nothing ----
+        let ip = frame.sender_ip;
+        if !ip.is_unspecified() {
+            if !segment.bindings.contains_key(&ip) {
+                evict(&mut segment.bindings);
+            }
+            let binding = segment.bindings.entry(ip).or_insert_with(|| ArpBinding {
+                iface: frame.iface.clone(),
+                ip: ip.to_string(),
+                mac: frame.sender_mac.clone(),
+                first_seen: now,
+                last_seen: now,
+                previous_mac: None,
+                changes: 0,
+                is_gateway: false,
+            });
+
+            if binding.mac != frame.sender_mac {
+                let recent = now - binding.last_seen < window;
+                let old = std::mem::replace(&mut binding.mac, frame.sender_mac.clone());
+                binding.previous_mac = Some(old.clone());
+                binding.changes += 1;
+
+                // The gateway has its own check below
+                if recent && segment.gateway != Some(ip) {
+                    signals.push((Signal::Conflict, vec![old, frame.sender_mac.clone()]));
+                }
+            }
+            binding.last_seen = now;
+
+            if segment.gateway == Some(ip) {
+                let known = segment.gateway_mac.as_ref();
+                if let Some(known) = known.filter(|known| **known != frame.sender_mac) {
+                    let macs = vec![known.clone(), frame.sender_mac.clone()];
+                    signals.push((Signal::GatewayChange, macs));
+                }
+                segment.gateway_mac = Some(frame.sender_mac.clone());
+            }
+        }
+
+        if !config.enabled {
+            return Vec::new();
+        }
+
+        // Each signal is alerted once per its own window
+        let window_of = |signal| match signal {
+            Signal::GratuitousFlood => flood_window,
+            _ => window,
+        };
+        segment
+            .alerted
+            .retain(|(signal, _), at| *at >= now - window_of(*signal));
+
+        let mut alerts = Vec::new();
+        for (signal, macs) in signals {
+            // A flood is one alert per segment, whoever sends it
+            let target = match signal {
+                Signal::GratuitousFlood => Ipv4Addr::UNSPECIFIED,
+                _ => ip,
+            };
+            if segment.alerted.contains_key(&(signal, target)) {
+                continue;
+            }
+            segment.alerted.insert((signal, target), now);
+
+            let score = match signal {
+                Signal::GratuitousFlood => segment.gratuitous.len() as f64,
+                _ => 1.0,
+            };
+
+            alerts.push(Verdict {
+                iface: frame.iface.clone(),
+                flow: FlowTuple {
+                    src_ip: ip.to_string(),
+                    src_port: 0,
+                    dst_ip: frame.target_ip.to_string(),
+                    dst_port: 0,
+                    protocol: 0,
+                },
+                label: signal.label().into(),
+                is_anomaly: true,
+                score,
+                model: Some(ModelSource::Arp),
+                flow_start: match signal {
+                    Signal::GratuitousFlood => segment.gratuitous.front().map(|(at, _)| *at),
+                    _ => None,
+                },
+                fwd_flags: None,
+                bytes: None,
+                bwd_packets: None,
+                period_secs: None,
+                domains: Vec::new(),
+                macs,
+                tls: None,
+                timestamp: now,
+            });
+        }
+
+        alerts
+    }
+
+    /// Bindings sorted by interface and address, optionally for one interface.
+    pub fn bindings(&self, iface: Option<&str>) -> Vec<ArpBinding> {
+        let segments = self.segments.lock().unwrap();
+
+        let mut bindings: Vec<ArpBinding> = segments
+            .iter()
+            .filter(|(name, _)| iface.is_none_or(|iface| *name == iface))
+            .flat_map(|(_, segment)| {
+                segment.bindings.iter().map(|(ip, binding)| ArpBinding {
+                    is_gateway: segment.gateway == Some(*ip),
+                    ..binding.clone()
+                })
+            })
+            .collect();
+        bindings.sort_by_key(|b| (b.iface.clone(), b.ip.parse::<Ipv4Addr>().ok()));
+        bindings
+    }
+}
+
+/// Makes room for a new binding by dropping the least recently seen.
+fn evict(bindings: &mut HashMap<Ipv4Addr, ArpBinding>) {
+    if bindings.len() < MAX_BINDINGS_PER_IFACE {
+        return;
+    }
+
+    let oldest = bindings
+        .iter()
+        .min_by_key(|(_, binding)| binding.last_seen)
+        .map(|(ip, _)| *ip);
+    if let Some(ip) = oldest {
+        bindings.remove(&ip);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GATEWAY: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+
+    fn frame(ms: i64, mac: &str, sender: Ipv4Addr, target: Ipv4Addr) -> ArpFrame {
+        ArpFrame {
+            iface: "eth0".into(),
+            timestamp: DateTime::from_timestamp_millis(1_700_000_000_000 + ms).unwrap(),
+            is_reply: true,
+            sender_mac: mac.into(),
+            sender_ip: sender,
+            target_mac: "00:00:5e:00:53:ff".into(),
+            target_ip: target,
+            gateway: Some(GATEWAY),
+        }
+    }
+
+    fn labels(alerts: &[Verdict]) -> Vec<&str> {
+        alerts.iter().map(|v| v.label.as_str()).collect()
+    }
+
+    #[test]
+    fn recent_binding_changing_mac_is_a_conflict() {
+        let monitor = ArpMonitor::default();
+        let config = ArpConfig::default();
+        let host = Ipv4Addr::new(10, 0, 0, 5);
+
+        assert!(monitor
+            .observe(&frame(0, "00:00:5e:00:53:01", host, GATEWAY), &config)
+            .is_empty());
+        let alerts = monitor.observe(&frame(60_000, "00:00:5e:00:53:02", host, GATEWAY), &config);
+
+        assert_eq!(labels(&alerts), ["ARP_SPOOFING"]);
+        assert_eq!(alerts[0].macs, ["00:00:5e:00:53:01", "00:00:5e:00:53:02"]);
+    }
+
+    #[test]
+    fn stale_binding_is_rebound_quietly() {
+        let monitor = ArpMonitor::default();
+        let config = ArpConfig::default();
+        let host = Ipv4Addr::new(10, 0, 0, 6);
+        let later = (config.conflict_window_secs as i64 + 1) * 1000;
+
+        monitor.observe(&frame(0, "00:00:5e:00:53:01", host, GATEWAY), &config);
+        let alerts = monitor.observe(&frame(later, "00:00:5e:00:53:02", host, GATEWAY), &config);
+        assert!(alerts.is_empty());
+
+        let bindings = monitor.bindings(Some("eth0"));
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings[0].mac, "00:00:5e:00:53:02");
+        assert_eq!(
+            bindings[0].previous_mac.as_deref(),
+            Some("00:00:5e:00:53:01")
+        );
+        assert_eq!(bindings[0].changes, 1);
+    }
+
+    #[test]
+    fn gateway_mac_is_relearned_when_the_route_moves() {
+        let monitor = ArpMonitor::default();
+        let config = ArpConfig::default();
+        let host = Ipv4Addr::new(10, 0, 0, 5);
+
+        monitor.observe(&frame(0, "00:00:5e:00:53:aa", GATEWAY, host), &config);
+        let alerts = monitor.observe(&frame(1000, "00:00:5e:00:53:bb", GATEWAY, host), &config);
+        // Reported as a gateway change only, not as a plain conflict too
+        assert_eq!(labels(&alerts), ["GATEWAY_MAC_CHANGED"]);
+        assert_eq!(alerts[0].macs, ["00:00:5e:00:53:aa", "00:00:5e:00:53:bb"]);
+
+        // A new default route: its gateway's first MAC is learned, not alerted
+        let router = Ipv4Addr::new(10, 0, 0, 254);
+        let mut moved = frame(2000, "00:00:5e:00:53:cc", router, host);
+        moved.gateway = Some(router);
+        assert!(monitor.observe(&moved, &config).is_empty());
+        moved.timestamp += Duration::seconds(1);
+        assert!(monitor.observe(&moved, &config).is_empty());
+
+        let gateways: Vec<String> = monitor
+            .bindings(None)
+            .into_iter()
+            .filter(|b| b.is_gateway)
+            .map(|b| b.ip)
+            .collect();
+        assert_eq!(gateways, ["10.0.0.254"]);
+    }
+
+    #[test]
+    fn gratuitous_flood_alerts_once_per_flood_window() {
+        let monitor = ArpMonitor::default();
+        let config = ArpConfig::default();
+
+        // Four announcements a second, each from its own host, for 30 s
+        let mut alerted = Vec::new();
+        for k in 0..120u8 {
+            let ip = Ipv4Addr::new(10, 0, 1, k);
+            let mac = format!("00:00:5e:00:54:{k:02x}");
+            let alerts = monitor.observe(&frame(k as i64 * 250, &mac, ip, ip), &config);
+            if !alerts.is_empty() {
+                assert_eq!(labels(&alerts), ["GRATUITOUS_ARP_FLOOD"]);
+                alerted.push(k);
+            }
+        }
+
+        // At the 20th message, then each time the previous alert leaves the window
+        assert_eq!(alerted, [19, 60, 101]);
+    }
+
+    #[test]
+    fn probes_from_the_unspecified_address_claim_nothing() {
+        let monitor = ArpMonitor::default();
+        let config = ArpConfig::default();
+        let host = Ipv4Addr::new(10, 0, 0, 5);
+
+        monitor.observe(&frame(0, "00:00:5e:00:53:01", host, GATEWAY), &config);
+        // Duplicate address detection (RFC 5227) by another device
+        let mut probe = frame(1000, "00:00:5e:00:53:02", Ipv4Addr::UNSPECIFIED, host);
+        probe.is_reply = false;
+        assert!(monitor.observe(&probe, &config).is_empty());
+
+        let bindings = monitor.bindings(None);
+        assert_eq!(bindings.len(), 1);
+        assert_eq!(bindings[0].ip, "10.0.0.5");
+        assert_eq!(bindings[0].mac, "00:00:5e:00:53:01");
+    }
+}
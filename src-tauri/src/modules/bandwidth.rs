@@ -0,0 +1,106 @@
+use crate::{Bandwidth, BandwidthSample};
+
+use chrono::Utc;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Instant;
+
+// Weight of the newest sample in the smoothed rate
+const EWMA_ALPHA: f64 = 0.3;
+// Samples kept per interface, one per monitor cycle whatever `monitor.interval_secs` is
+pub const HISTORY_LEN: usize = 60;
+
+fn mbps(bytes: u64, secs: f64) -> f64 {
+    (bytes as f64 * 8.0) / 1_000_000.0 / secs
+}
+
+#[derive(Debug)]
+struct RateState {
+    rx_bytes: u64,
+    tx_bytes: u64,
+    at: Instant,
+    bandwidth: Bandwidth,
+    history: VecDeque<BandwidthSample>,
+}
+
+/// Turns the OS byte counters into rates by remembering the previous
+/// sample of every interface.
+#[derive(Debug, Default)]
+pub struct RateTracker {
+    states: Mutex<HashMap<String, RateState>>,
+}
+
+impl RateTracker {
+    /// Feeds the current totals for `iface` and returns its rates and history.
+    /// The first sample of an interface only establishes a baseline.
+    pub fn sample(&self, iface: &str, rx_bytes: u64, tx_bytes: u64) -> (Bandwidth, Vec<BandwidthSample>) {
+        let now = Instant::now();
+        let mut states = self.states.lock().unwrap();
+
+        let state = match states.get_mut(iface) {
+            Some(state) => state,
+            None => {
+                states.insert(
+                    iface.to_string(),
+                    RateState {
+                        rx_bytes,
+                        tx_bytes,
+                        at: now,
+                        bandwidth: Bandwidth::new(),
+                        history: VecDeque::with_capacity(HISTORY_LEN),
+                    },
+                );
+                return (Bandwidth::new(), Vec::new());
+            }
+        };
+
+        let secs = now.duration_since(state.at).as_secs_f64();
+        if secs <= 0.0 {
+            return (state.bandwidth.clone(), state.history.iter().cloned().collect());
+        }
+
+        // Counters go backwards when a driver resets or the adapter is re-plugged
+        let download = mbps(rx_bytes.saturating_sub(state.rx_bytes), secs);
+        let upload = mbps(tx_bytes.saturating_sub(state.tx_bytes), secs);
+
+        let bw = &mut state.bandwidth;
+        let first_rate = state.history.is_empty();
+        bw.download = download;
+        bw.upload = upload;
+        bw.download_avg = if first_rate {
+            download
+        } else {
+            EWMA_ALPHA * download + (1.0 - EWMA_ALPHA) * bw.download_avg
+        };
+        bw.upload_avg = if first_rate {
+            upload
+        } else {
+            EWMA_ALPHA * upload + (1.0 - EWMA_ALPHA) * bw.upload_avg
+        };
+        bw.peak_download = bw.peak_download.max(download);
+        bw.peak_upload = bw.peak_upload.max(upload);
+
+        if state.history.len() == HISTORY_LEN {
+            state.history.pop_front();
+        }
+        state.history.push_back(BandwidthSample {
+            timestamp: Utc::now(),
+            download,
+            upload,
+        });
+
+        state.rx_bytes = rx_bytes;
+        state.tx_bytes = tx_bytes;
+        state.at = now;
+
+        (state.bandwidth.clone(), state.history.iter().cloned().collect())
+    }
+
+    /// Drops interfaces that are no longer present.
+    pub fn retain(&self, present: &[String]) {
+        self.states
+            .lock()
+            .unwrap()
+            .retain(|name, _| present.contains(name));
+    }
+}
@@ -0,0 +1,376 @@
+use crate::modules::config::BeaconConfig;
+use crate::modules::packets::{PROTO_TCP, PROTO_UDP};
+use crate::modules::privacy::{classify, AddrScope};
+use crate::modules::protocol::{FlowTuple, ModelSource, Verdict};
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::Mutex;
+
+// Bounds memory on hosts that talk to many destinations
+const MAX_PAIRS: usize = 8192;
+const MAX_CONNECTIONS_PER_PAIR: usize = 64;
+
+// Connections this close together are one call home, e.g. DNS + HTTPS
+const BURST_SECS: f64 = 1.0;
+
+// A pair that keeps beaconing is reported again after this long
+const REALERT_SECS: i64 = 3600;
+
+// (internal host, external destination, destination port)
+type PairKey = (String, String, u16);
+
+#[derive(Debug)]
+struct Connection {
+    at: DateTime<Utc>,
+    bytes: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+struct Pair {
+    // Sorted by start time
+    connections: VecDeque<Connection>,
+    alerted: Option<DateTime<Utc>>,
+}
+
+impl Pair {
+    fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.connections.back().map(|c| c.at)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Beacon {
+    period_secs: f64,
+    confidence: f64,
+}
+
+/// Where `ip` sits, `None` when it doesn't parse. Hosts an address doesn't
+/// identify are internal, the others external.
+fn scope(ip: &str) -> Option<AddrScope> {
+    ip.parse::<IpAddr>().ok().map(|ip| classify(ip.to_canonical()))
+}
+
+/// Cross-flow detector: per internal host, external destination and port,
+/// looks for connections at a steady interval with steady sizes.
+#[derive(Debug, Default)]
+pub struct BeaconDetector {
+    pairs: Mutex<HashMap<PairKey, Pair>>,
+}
+
+impl BeaconDetector {
+    /// Feeds one classified flow. Returns an anomalous verdict, with the
+    /// period and the confidence as score, when its pair looks like a beacon.
+    pub fn observe(&self, verdict: &Verdict, config: &BeaconConfig) -> Option<Verdict> {
+        let flow = &verdict.flow;
+        if !config.enabled
+            || !matches!(flow.protocol, PROTO_TCP | PROTO_UDP)
+            || scope(&flow.src_ip).is_none_or(AddrScope::is_identifying)
+            || !scope(&flow.dst_ip).is_some_and(AddrScope::is_identifying)
+        {
+            return None;
+        }
+
+        let now = verdict.timestamp;
+        let key = (flow.src_ip.clone(), flow.dst_ip.clone(), flow.dst_port);
+        let mut pairs = self.pairs.lock().unwrap();
+
+        if !pairs.contains_key(&key) {
+            let idle = Duration::seconds((config.max_period_secs * 2.0) as i64);
+            evict(&mut pairs, now - idle);
+        }
+        let pair = pairs.entry(key).or_default();
+
+        // Flows are reported when they end, so starts can arrive out of order
+        let at = verdict.flow_start.unwrap_or(now);
+        let index = pair.connections.partition_point(|c| c.at <= at);
+        pair.connections.insert(
+            index,
+            Connection {
+                at,
+                bytes: verdict.bytes,
+            },
+        );
+        if pair.connections.len() > MAX_CONNECTIONS_PER_PAIR {
+            pair.connections.pop_front();
+        }
+
+        if pair
+            .alerted
+            .is_some_and(|at| now - at < Duration::seconds(REALERT_SECS))
+        {
+            return None;
+        }
+
+        let beacon = analyse(&pair.connections, config)?;
+        pair.alerted = Some(now);
+
+        Some(Verdict {
+            iface: verdict.iface.clone(),
+            flow: FlowTuple {
+                src_ip: flow.src_ip.clone(),
+                src_port: 0,
+                dst_ip: flow.dst_ip.clone(),
+                dst_port: flow.dst_port,
+                protocol: flow.protocol,
+            },
+            label: "BEACONING".into(),
+            is_anomaly: true,
+            score: beacon.confidence,
+            model: Some(ModelSource::Beacon),
+            flow_start: pair.connections.front().map(|c| c.at),
+            fwd_flags: None,
+            bytes: None,
+            bwd_packets: None,
+            period_secs: Some(beacon.period_secs),
+            domains: Vec::new(),
+            macs: Vec::new(),
+            tls: None,
+            timestamp: now,
+        })
+    }
+}
+
+/// Judges the connections of one pair. Spreads are median absolute
+/// deviations, so a missed or an extra call home doesn't hide a beacon.
+fn analyse(connections: &VecDeque<Connection>, config: &BeaconConfig) -> Option<Beacon> {
+    // ---- Collapse bursts into calls ----
+    let mut calls: Vec<(DateTime<Utc>, Option<u64>)> = Vec::new();
+    for conn in connections {
+        match calls.last_mut() {
+            Some((at, bytes)) if seconds(conn.at - *at) < BURST_SECS => {
+                *bytes = bytes.zip(conn.bytes).map(|(a, b)| a + b);
+            }
+            _ => calls.push((conn.at, conn.bytes)),
+        }
+    }
+    if calls.len() < config.min_connections {
+        return None;
+    }
+
+    // ---- Timing ----
+    let intervals: Vec<f64> = calls.windows(2).map(|w| seconds(w[1].0 - w[0].0)).collect();
+    let period = median(&intervals);
+    if !(config.min_period_secs..=config.max_period_secs).contains(&period) {
+        return None;
+    }
+    let jitter = deviation(&intervals, period) / period;
+    if jitter > config.max_jitter {
+        return None;
+    }
+
+    // ---- Sizes, when at least half of them are known ----
+    let sizes: Vec<f64> = calls
+        .iter()
+        .filter_map(|(_, b)| b.map(|b| b as f64))
+        .collect();
+    let size_jitter = if sizes.len() * 2 >= calls.len() {
+        let size = median(&sizes);
+        (size > 0.0).then(|| deviation(&sizes, size) / size)
+    } else {
+        None
+    };
+    if size_jitter.is_some_and(|s| s > config.max_size_jitter) {
+        return None;
+    }
+
+    // Unknown sizes neither help nor hurt
+    let regularity = 1.0 - jitter / config.max_jitter;
+    let steadiness = size_jitter.map_or(0.5, |s| 1.0 - s / config.max_size_jitter);
+    let coverage = (intervals.len() as f64 / (2 * config.min_connections) as f64).min(1.0);
+    let confidence = 0.5 * regularity + 0.3 * steadiness + 0.2 * coverage;
+
+    Some(Beacon {
+        period_secs: period,
+        confidence: confidence.clamp(0.0, 1.0),
+    })
+}
+
+fn seconds(d: Duration) -> f64 {
+    d.num_milliseconds() as f64 / 1000.0
+}
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let mid = sorted.len() / 2;
+    match sorted.len() {
+        0 => 0.0,
+        n if n % 2 == 0 => (sorted[mid - 1] + sorted[mid]) / 2.0,
+        _ => sorted[mid],
+    }
+}
+
+fn deviation(values: &[f64], center: f64) -> f64 {
+    let spread: Vec<f64> = values.iter().map(|v| (v - center).abs()).collect();
+    median(&spread)
+}
+
+/// Makes room for a new pair: drops idle ones, then the least recent.
+fn evict(pairs: &mut HashMap<PairKey, Pair>, cutoff: DateTime<Utc>) {
+    if pairs.len() < MAX_PAIRS {
+        return;
+    }
+
+    pairs.retain(|_, pair| pair.last_seen().is_some_and(|at| at >= cutoff));
+
+    if pairs.len() >= MAX_PAIRS {
+        let oldest = pairs
+            .iter()
+            .min_by_key(|(_, pair)| pair.last_seen())
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            pairs.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST: &str = "192.168.1.20";
+    const SERVER: &str = "93.184.216.34";
+
+    // One connection starting `start_ms` after the epoch of the test
+    fn call(src: &str, dst: &str, start_ms: i64, bytes: u64) -> Verdict {
+        let start = DateTime::from_timestamp_millis(1_700_000_000_000 + start_ms).unwrap();
+        Verdict {
+            iface: "eth0".into(),
+            flow: FlowTuple {
+                src_ip: src.into(),
+                src_port: 50000,
+                dst_ip: dst.into(),
+                dst_port: 443,
+                protocol: PROTO_TCP,
+            },
+            label: "BENIGN".into(),
+            is_anomaly: false,
+            score: 0.1,
+            model: Some(ModelSource::Xgb),
+            flow_start: Some(start),
+            fwd_flags: None,
+            bytes: Some(bytes),
+            bwd_packets: Some(4),
+            period_secs: None,
+            domains: Vec::new(),
+            macs: Vec::new(),
+            tls: None,
+            timestamp: start + Duration::seconds(1),
+        }
+    }
+
+    // Index and period of each call that raised an alert
+    fn alerts_at(starts_secs: &[f64]) -> Vec<(usize, f64)> {
+        let detector = BeaconDetector::default();
+        let config = BeaconConfig::default();
+
+        starts_secs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, secs)| {
+                let verdict = call(HOST, SERVER, (secs * 1000.0) as i64, 900);
+                detector
+                    .observe(&verdict, &config)
+                    .map(|alert| (i, alert.period_secs.unwrap()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn steady_calls_are_a_beacon() {
+        let starts: Vec<f64> = (0..8).map(|i| i as f64 * 60.0).collect();
+        let alerts = alerts_at(&starts);
+
+        assert_eq!(alerts.len(), 1);
+        let (index, period) = alerts[0];
+        assert_eq!(index, 7);
+        assert!((period - 60.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn jittery_calls_are_not() {
+        let intervals = [20.0, 95.0, 40.0, 80.0, 55.0, 110.0, 30.0, 70.0, 45.0, 100.0];
+        let starts: Vec<f64> = std::iter::once(0.0)
+            .chain(intervals.iter().scan(0.0, |at, i| {
+                *at += i;
+                Some(*at)
+            }))
+            .collect();
+
+        assert!(alerts_at(&starts).is_empty());
+    }
+
+    #[test]
+    fn one_missed_call_still_alerts() {
+        let starts = [0.0, 60.0, 120.0, 240.0, 300.0, 360.0, 420.0, 480.0];
+        let alerts = alerts_at(&starts);
+
+        assert_eq!(alerts.len(), 1);
+        assert!((alerts[0].1 - 60.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn only_internal_to_external_pairs_are_watched() {
+        let detector = BeaconDetector::default();
+        let config = BeaconConfig::default();
+        let pairs = [
+            (HOST, "10.0.0.1"),
+            ("8.8.8.8", SERVER),
+            ("not-an-ip", SERVER),
+            (HOST, "not-an-ip"),
+        ];
+
+        for (src, dst) in pairs {
+            for i in 0..10 {
+                assert!(detector
+                    .observe(&call(src, dst, i * 60_000, 900), &config)
+                    .is_none());
+            }
+        }
+        assert!(detector.pairs.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn median_and_deviation() {
+        assert_eq!(median(&[]), 0.0);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+        // One outlier moves neither
+        assert_eq!(median(&[60.0, 61.0, 59.0, 60.0, 600.0]), 60.0);
+        assert_eq!(deviation(&[1.0, 2.0, 3.0, 4.0, 100.0], 3.0), 1.0);
+    }
+
+    #[test]
+    fn bursts_collapse_into_one_call() {
+        // A lookup and a connection 300 ms apart, every minute
+        let starts: Vec<f64> = (0..8)
+            .flat_map(|i| [i as f64 * 60.0, i as f64 * 60.0 + 0.3])
+            .collect();
+        let alerts = alerts_at(&starts);
+
+        assert_eq!(alerts.len(), 1);
+        assert!((alerts[0].1 - 60.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn flows_reported_out_of_order_are_sorted() {
+        let starts = [0.0, 120.0, 60.0, 240.0, 180.0, 360.0, 300.0, 420.0];
+        let alerts = alerts_at(&starts);
+
+        assert_eq!(alerts.len(), 1);
+        assert!((alerts[0].1 - 60.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn a_beacon_is_reported_again_after_the_realert_window() {
+        let starts: Vec<f64> = (0..80).map(|i| i as f64 * 60.0).collect();
+        let indices: Vec<usize> = alerts_at(&starts).into_iter().map(|(i, _)| i).collect();
+
+        // First at the 8th call, next once REALERT_SECS have gone by
+        let next = 7 + (REALERT_SECS / 60) as usize;
+        assert_eq!(indices, [7, next]);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::modules::flows::{Flow, FlowTable};
+use crate::modules::networks::{default_gateway, CaptureDevice};
+use crate::modules::packets::{link_payload, parse_packet, Packet, ETHERTYPE_ARP, PROTO_UDP};
+use crate::modules::pipeline::Pipeline;
+use crate::modules::protocol::{
+    ArpPacket, DnsPacket, Heartbeat, WorkerFault, WorkerMessage, WorkerStats,
+};
+use crate::modules::supervisor::WorkerOutput;
+use crate::AppState;
+
+use chrono::{DateTime, Utc};
+use pcap::{Activated, Active, Capture, Device};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const SNAPLEN: i32 = 65535;
+const READ_TIMEOUT_MS: i32 = 1000;
+const SWEEP_INTERVAL_US: i64 = 1_000_000;
+
+// ---- Live capture housekeeping ----
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+const STATS_INTERVAL: Duration = Duration::from_secs(60);
+const GATEWAY_REFRESH: Duration = Duration::from_secs(60);
+
+const DNS_PORT: u16 = 53;
+const MAX_DNS_PACKET: usize = 1500;
+
+// A capture notices `stop` within one read timeout, flushing open flows may take longer
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How a live capture is opened. Captures restart when these change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureOptions {
+    pub bpf_filter: String,
+    pub promiscuous: bool,
+    // ARP is captured for `arp.rs` whatever the filter says
+    pub arp: bool,
+}
+
+impl CaptureOptions {
+    fn filter(&self) -> String {
+        match self.bpf_filter.trim() {
+            "" => String::new(),
+            filter if self.arp => format!("({filter}) or arp"),
+            filter => filter.to_string(),
+        }
+    }
+}
+
+pub fn open_live(
+    iface: &str,
+    bpf_filter: &str,
+    promiscuous: bool,
+) -> Result<Capture<Active>, String> {
+    let mut cap = Capture::from_device(iface)
+        .map_err(|e| format!("Unknown capture device {iface}: {e}"))?
+        .promisc(promiscuous)
+        .snaplen(SNAPLEN)
+        .timeout(READ_TIMEOUT_MS)
+        .immediate_mode(true)
+        .open()
+        .map_err(|e| format!("Failed to open {iface}: {e}"))?;
+
+    cap.filter(bpf_filter, true)
+        .map_err(|e| format!("Invalid BPF filter '{bpf_filter}': {e}"))?;
+
+    Ok(cap)
+}
+
+/// Capture devices as libpcap lists them, for when `helper.py` can't run.
+/// libpcap doesn't report MACs, so these match interfaces by name or address.
+pub fn list_devices() -> Vec<CaptureDevice> {
+    let devices = Device::list()
+        .inspect_err(|e| eprintln!("Failed to list capture devices: {e}"))
+        .unwrap_or_default();
+
+    devices
+        .into_iter()
+        .map(|device| {
+            let addrs = device.addresses.iter().map(|a| a.addr);
+            CaptureDevice {
+                ipv4: addrs.clone().find(IpAddr::is_ipv4).map(|ip| ip.to_string()),
+                ipv6: addrs.filter(IpAddr::is_ipv6).map(|ip| ip.to_string()).collect(),
+                mac: None,
+                name: device.name,
+            }
+        })
+        .collect()
+}
+
+/// What `meter_with` reports while it runs.
+pub enum Metered<'a> {
+    // Every captured frame, with its IP packet when it carries one
+    Frame {
+        data: &'a [u8],
+        packet: Option<&'a Packet<'a>>,
+        timestamp_us: i64,
+    },
+    Flow(Box<Flow>),
+    // After every read, so periodic work also runs on a quiet link
+    Tick,
+}
+
+/// Meters every packet of `cap` into bidirectional flows and hands each
+/// finished flow to `on_flow`. Runs until the capture ends or `stop` is set,
+/// then flushes whatever is still open.
+pub fn meter<T: Activated + ?Sized>(
+    cap: &mut Capture<T>,
+    stop: &AtomicBool,
+    mut on_flow: impl FnMut(Flow),
+) -> Result<(), String> {
+    meter_with(cap, stop, |event| {
+        if let Metered::Flow(flow) = event {
+            on_flow(*flow);
+        }
+    })
+}
+
+/// `meter`, also reporting every frame and every read to `on_event`.
+pub fn meter_with<T: Activated + ?Sized>(
+    cap: &mut Capture<T>,
+    stop: &AtomicBool,
+    mut on_event: impl FnMut(Metered),
+) -> Result<(), String> {
+    let linktype = cap.get_datalink().0;
+    let mut table = FlowTable::new();
+    let mut last_sweep = 0;
+
+    while !stop.load(Ordering::Relaxed) {
+        // ---- Capture clock: packet time, or wall time when the link is quiet ----
+        let now = match cap.next_packet() {
+            Ok(raw) => {
+                // A C long, only 32 bits on Windows
+                #[allow(clippy::unnecessary_cast)]
+                let ts = raw.header.ts.tv_sec as i64 * 1_000_000 + raw.header.ts.tv_usec as i64;
+
+                let packet = parse_packet(linktype, raw.data, ts);
+
+                on_event(Metered::Frame {
+                    data: raw.data,
+                    packet: packet.as_ref(),
+                    timestamp_us: ts,
+                });
+
+                if let Some(flow) = packet.and_then(|packet| table.push(&packet)) {
+                    on_event(Metered::Flow(Box::new(flow)));
+                }
+
+                ts
+            }
+            Err(pcap::Error::TimeoutExpired) => Utc::now().timestamp_micros(),
+            Err(pcap::Error::NoMorePackets) => break,
+            Err(e) => return Err(e.to_string()),
+        };
+
+        // ---- Sweep idle / active timeouts ----
+        if now - last_sweep >= SWEEP_INTERVAL_US {
+            for flow in table.expire(now) {
+                on_event(Metered::Flow(Box::new(flow)));
+            }
+            last_sweep = now;
+        }
+
+        on_event(Metered::Tick);
+    }
+
+    for flow in table.drain() {
+        on_event(Metered::Flow(Box::new(flow)));
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------
+// LIVE CAPTURE
+// ---------------------------------------------------------------------
+fn dns_packet(iface: &str, packet: &Packet) -> Option<DnsPacket> {
+    if packet.protocol != PROTO_UDP || ![packet.src_port, packet.dst_port].contains(&DNS_PORT) {
+        return None;
+    }
+
+    Some(DnsPacket {
+        iface: iface.to_string(),
+        src_ip: packet.src_ip.to_string(),
+        dst_ip: packet.dst_ip.to_string(),
+        payload: packet.payload[..packet.payload.len().min(MAX_DNS_PACKET)].to_vec(),
+        timestamp: DateTime::from_timestamp_micros(packet.timestamp_us).unwrap_or_else(Utc::now),
+    })
+}
+
+fn arp_packet(
+    iface: &str,
+    linktype: i32,
+    data: &[u8],
+    timestamp_us: i64,
+    gateway: Option<Ipv4Addr>,
+) -> Option<ArpPacket> {
+    let (ethertype, arp) = link_payload(linktype, data)?;
+    (ethertype == ETHERTYPE_ARP).then(|| ArpPacket {
+        iface: iface.to_string(),
+        payload: arp.to_vec(),
+        gateway,
+        timestamp: DateTime::from_timestamp_micros(timestamp_us).unwrap_or_else(Utc::now),
+    })
+}
+
+/// Meters `device` and classifies every finished flow, sending verdicts,
+/// DNS and ARP packets, heartbeats and stats into `pipeline` on behalf of `output`
+/// until `stop` is set. A capture that fails also reports why before it returns.
+pub fn capture_live(
+    device: &str,
+    options: &CaptureOptions,
+    state: &AppState,
+    pipeline: &Pipeline,
+    output: &WorkerOutput,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let send = |message: WorkerMessage| pipeline.send(output, message);
+
+    let mut stats = WorkerStats {
+        iface: device.to_string(),
+        flows: 0,
+        anomalies: 0,
+        errors: 0,
+        timestamp: Utc::now(),
+    };
+    let mut gateway = options.arp.then(|| default_gateway(device)).flatten();
+    let mut gateway_at = Instant::now();
+    let mut heartbeat_at: Option<Instant> = None;
+    let mut stats_at = Instant::now();
+
+    let result = open_live(device, &options.filter(), options.promiscuous).and_then(|mut cap| {
+        let linktype = cap.get_datalink().0;
+
+        meter_with(&mut cap, stop, |event| match event {
+            // Decoded by the state updater, which would drop them while paused
+            Metered::Frame { .. } if output.is_paused() => {}
+            Metered::Frame {
+                data,
+                packet,
+                timestamp_us,
+            } => {
+                let message = match packet {
+                    Some(packet) => dns_packet(device, packet).map(WorkerMessage::Dns),
+                    None if options.arp => {
+                        arp_packet(device, linktype, data, timestamp_us, gateway)
+                            .map(WorkerMessage::Arp)
+                    }
+                    None => None,
+                };
+                if let Some(message) = message {
+                    send(message);
+                }
+            }
+            Metered::Flow(flow) => {
+                stats.flows += 1;
+
+                // Verdicts would be dropped anyway, and the models may still be loading
+                let detector = state.detector.read().unwrap().clone();
+                let Some(detector) = detector.filter(|_| !output.is_paused()) else {
+                    return;
+                };
+
+                match detector.classify(device, &flow) {
+                    Some(verdict) => {
+                        stats.anomalies += verdict.is_anomaly as u64;
+                        send(WorkerMessage::Verdict(verdict));
+                    }
+                    None if detector.is_ready() => stats.errors += 1,
+                    None => {}
+                }
+            }
+            Metered::Tick => {
+                if heartbeat_at.is_none_or(|at| at.elapsed() >= HEARTBEAT_INTERVAL) {
+                    let beat = Heartbeat {
+                        iface: device.to_string(),
+                        timestamp: Utc::now(),
+                    };
+                    send(WorkerMessage::Heartbeat(beat));
+                    heartbeat_at = Some(Instant::now());
+                }
+                if stats_at.elapsed() >= STATS_INTERVAL {
+                    stats.timestamp = Utc::now();
+                    send(WorkerMessage::Stats(stats.clone()));
+                    stats_at = Instant::now();
+                }
+                if options.arp && gateway_at.elapsed() >= GATEWAY_REFRESH {
+                    gateway = default_gateway(device);
+                    gateway_at = Instant::now();
+                }
+            }
+        })
+    });
+
+    if let Err(e) = &result {
+        send(WorkerMessage::Error(WorkerFault {
+            iface: device.to_string(),
+            message: e.clone(),
+            fatal: true,
+            timestamp: Utc::now(),
+        }));
+    }
+
+    result
+}
+
+/// A thread running one capture device until it is stopped.
+#[derive(Debug)]
+pub struct LiveCapture {
+    device: String,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Result<(), String>>,
+}
+
+impl LiveCapture {
+    /// Runs `capture` for `device` on a new thread, passing it the flag
+    /// `stop` sets.
+    pub fn start(
+        device: &str,
+        capture: impl FnOnce(&AtomicBool) -> Result<(), String> + Send + 'static,
+    ) -> io::Result<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let flag = Arc::clone(&stop);
+        let thread = thread::Builder::new()
+            .name(format!("capture {device}"))
+            .spawn(move || capture(&flag))?;
+
+        Ok(Self {
+            device: device.to_string(),
+            stop,
+            thread,
+        })
+    }
+
+    /// True once the thread has ended on its own, e.g. the device went away.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Stops the capture and waits for its thread, so the device can be
+    /// opened again right away. Gives up waiting after `STOP_TIMEOUT`.
+    pub fn stop(self) -> Result<(), String> {
+        self.stop.store(true, Ordering::Relaxed);
+
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while !self.thread.is_finished() {
+            if Instant::now() >= deadline {
+                return Err(format!("capture on {} did not stop in time", self.device));
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        self.thread
+            .join()
+            .unwrap_or_else(|_| Err(format!("capture on {} panicked", self.device)))
+    }
+}
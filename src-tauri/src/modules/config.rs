@@ -0,0 +1,599 @@
+use crate::modules::capture::CaptureOptions;
+use crate::modules::detector::Detector;
+use crate::modules::helpers::glob_match;
+use crate::modules::monitoring::InterfaceControl;
+use crate::modules::privacy::{policy_from_env, PrivacyPolicy};
+use crate::modules::protocol::{ModelSource, Verdict};
+use crate::modules::storage::{retention_from_env, DEFAULT_RETENTION_DAYS};
+use crate::modules::supervisor::stop_workers;
+use crate::AppState;
+
+use notify::{RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+pub const CONFIG_FILE: &str = "config.toml";
+
+// Editors save in several steps (truncate, write, rename); let them settle
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(300);
+
+// ---------------------------------------------------------------------
+// SCHEMA
+//
+// Every section and field is optional in the file; missing ones take the
+// defaults below. Unknown keys are rejected so typos don't pass silently.
+// ---------------------------------------------------------------------
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitorConfig {
+    /// Seconds between interface scans.
+    pub interval_secs: u64,
+    /// Extra attempts at listing capture devices on startup.
+    pub device_retries: u32,
+    /// Globs (`*`, `?`) over interface or capture device names to run
+    /// workers on. Empty means all.
+    pub include: Vec<String>,
+    /// Globs never monitored, even when included.
+    pub exclude: Vec<String>,
+    /// Start/stop/pause choices by interface name, overriding the globs.
+    pub interfaces: BTreeMap<String, InterfaceControl>,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            device_retries: 5,
+            include: Vec::new(),
+            // Loopback and virtual bridges carry no traffic worth a worker
+            exclude: ["lo", "lo0", "*loopback*", "docker*", "br-*", "veth*", "virbr*"]
+                .map(String::from)
+                .to_vec(),
+            interfaces: BTreeMap::new(),
+        }
+    }
+}
+
+impl MonitorConfig {
+    /// Whether the globs select an interface known as `name` (capture device `device`).
+    pub fn monitors(&self, name: &str, device: &str) -> bool {
+        let listed = |globs: &[String]| {
+            globs
+                .iter()
+                .any(|glob| glob_match(glob, name) || glob_match(glob, device))
+        };
+
+        (self.include.is_empty() || listed(&self.include)) && !listed(&self.exclude)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CaptureConfig {
+    pub bpf_filter: String,
+    pub promiscuous: bool,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            bpf_filter: "ip or ip6".into(),
+            promiscuous: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DetectionConfig {
+    /// XGBoost verdicts below this class probability are not alerted on.
+    pub min_confidence: f64,
+    /// Replaces the reconstruction-error threshold shipped with the LSTM.
+    pub lstm_threshold: Option<f64>,
+}
+
+impl DetectionConfig {
+    /// Downgrades anomalies the classifier is not confident enough about.
+    pub fn apply(&self, verdict: &mut Verdict) {
+        if verdict.is_anomaly
+            && verdict.model == Some(ModelSource::Xgb)
+            && verdict.score < self.min_confidence
+        {
+            verdict.is_anomaly = false;
+        }
+    }
+}
+
+/// Port-scan and host-sweep detection over the flows of each source address.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanConfig {
+    pub enabled: bool,
+    pub window_secs: u64,
+    /// Distinct ports on one host.
+    pub port_threshold: usize,
+    /// Distinct hosts on one port that refused or never answered.
+    pub host_threshold: usize,
+    /// Distinct host/port pairs the source never completed a handshake with.
+    pub syn_threshold: usize,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 60,
+            port_threshold: 100,
+            host_threshold: 50,
+            syn_threshold: 50,
+        }
+    }
+}
+
+/// Periodic call-home detection over the flows of each
+/// internal host / external destination / port.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BeaconConfig {
+    pub enabled: bool,
+    /// Connections needed before the intervals are judged.
+    pub min_connections: usize,
+    pub min_period_secs: f64,
+    pub max_period_secs: f64,
+    /// Largest spread of the intervals, as a fraction of the period.
+    pub max_jitter: f64,
+    /// Largest spread of the flow sizes, as a fraction of their median.
+    pub max_size_jitter: f64,
+}
+
+impl Default for BeaconConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_connections: 8,
+            min_period_secs: 5.0,
+            max_period_secs: 3600.0,
+            max_jitter: 0.15,
+            max_size_jitter: 0.25,
+        }
+    }
+}
+
+/// DNS tunneling and DGA detection over the DNS traffic of each client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DnsConfig {
+    pub enabled: bool,
+    pub window_secs: u64,
+    /// Labels longer than this look like encoded data.
+    pub max_label_len: usize,
+    /// Shannon entropy, in bits per character, above which a label looks random.
+    pub entropy_threshold: f64,
+    /// Distinct suspicious names under one domain.
+    pub tunnel_threshold: usize,
+    /// TXT and NULL queries for one domain.
+    pub txt_threshold: usize,
+    /// Distinct names answered with NXDOMAIN.
+    pub nxdomain_threshold: usize,
+}
+
+impl Default for DnsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 300,
+            max_label_len: 50,
+            entropy_threshold: 3.0,
+            tunnel_threshold: 10,
+            txt_threshold: 30,
+            nxdomain_threshold: 20,
+        }
+    }
+}
+
+/// ARP spoofing detection on each monitored interface's segment.
+/// Turning it off also stops capturing ARP.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArpConfig {
+    pub enabled: bool,
+    /// A binding seen this recently that changes MAC is a conflict.
+    pub conflict_window_secs: u64,
+    pub flood_window_secs: u64,
+    /// Gratuitous ARP messages per window on one segment.
+    pub flood_threshold: usize,
+}
+
+impl Default for ArpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            conflict_window_secs: 300,
+            flood_window_secs: 10,
+            flood_threshold: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// JA3, JA3S or JA4 fingerprints to alert on, one per line with an
+    /// optional description. Edits are picked up without a restart, and a
+    /// missing file is reported and read once it appears.
+    pub blocklist: Option<PathBuf>,
+}
+
+/// `None` keeps the bundled location.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// The interpreter only; scripts come from `scripts_dir`.
+    /// Read at startup.
+    pub python_dir: Option<PathBuf>,
+    /// Read at startup.
+    pub scripts_dir: Option<PathBuf>,
+    /// Reloaded whenever it changes; clearing it goes back to the bundled
+    /// models.
+    pub models_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// Days of history kept, 0 keeping it forever.
+    /// `SENTINELAI_RETENTION_DAYS`, when set, takes precedence.
+    pub retention_days: u32,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: DEFAULT_RETENTION_DAYS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrivacyConfig {
+    /// `SENTINELAI_PRIVACY`, when set, takes precedence.
+    pub policy: PrivacyPolicy,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub monitor: MonitorConfig,
+    pub capture: CaptureConfig,
+    pub detection: DetectionConfig,
+    pub scan: ScanConfig,
+    pub beacon: BeaconConfig,
+    pub dns: DnsConfig,
+    pub tls: TlsConfig,
+    pub arp: ArpConfig,
+    pub paths: PathsConfig,
+    pub storage: StorageConfig,
+    pub privacy: PrivacyConfig,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read { path: PathBuf, source: io::Error },
+    Parse { path: PathBuf, source: toml::de::Error },
+    Invalid { field: &'static str, reason: String },
+    Serialize(toml::ser::Error),
+    Write { path: PathBuf, source: io::Error },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(f, "Failed to read {}: {source}", path.display())
+            }
+            ConfigError::Parse { path, source } => {
+                write!(f, "Invalid config {}: {source}", path.display())
+            }
+            ConfigError::Invalid { field, reason } => write!(f, "Invalid {field}: {reason}"),
+            ConfigError::Serialize(e) => write!(f, "Failed to serialize config: {e}"),
+            ConfigError::Write { path, source } => {
+                write!(f, "Failed to write {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read { source, .. } | ConfigError::Write { source, .. } => Some(source),
+            ConfigError::Parse { source, .. } => Some(source),
+            ConfigError::Serialize(e) => Some(e),
+            ConfigError::Invalid { .. } => None,
+        }
+    }
+}
+
+fn invalid(field: &'static str, reason: impl Into<String>) -> Result<(), ConfigError> {
+    Err(ConfigError::Invalid {
+        field,
+        reason: reason.into(),
+    })
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(1..=3600).contains(&self.monitor.interval_secs) {
+            return invalid("monitor.interval_secs", "must be between 1 and 3600");
+        }
+        if self.monitor.device_retries > 60 {
+            return invalid("monitor.device_retries", "must be at most 60");
+        }
+        let mut globs = self.monitor.include.iter().chain(&self.monitor.exclude);
+        if globs.any(|glob| glob.trim().is_empty()) {
+            return invalid("monitor.include/exclude", "patterns must not be empty");
+        }
+        if self.monitor.interfaces.keys().any(|name| name.trim().is_empty()) {
+            return invalid("monitor.interfaces", "interface names must not be empty");
+        }
+
+        if self.capture.bpf_filter.trim().is_empty() {
+            return invalid("capture.bpf_filter", "must not be empty");
+        }
+
+        if !(0.0..=1.0).contains(&self.detection.min_confidence) {
+            return invalid("detection.min_confidence", "must be between 0 and 1");
+        }
+        if let Some(threshold) = self.detection.lstm_threshold {
+            if !threshold.is_finite() || threshold <= 0.0 {
+                return invalid("detection.lstm_threshold", "must be a positive number");
+            }
+        }
+
+        if !(1..=3600).contains(&self.scan.window_secs) {
+            return invalid("scan.window_secs", "must be between 1 and 3600");
+        }
+        let thresholds = [
+            ("scan.port_threshold", self.scan.port_threshold),
+            ("scan.host_threshold", self.scan.host_threshold),
+            ("scan.syn_threshold", self.scan.syn_threshold),
+        ];
+        for (field, threshold) in thresholds {
+            if threshold < 2 {
+                return invalid(field, "must be at least 2");
+            }
+        }
+
+        let beacon = &self.beacon;
+        if beacon.min_connections < 4 {
+            return invalid("beacon.min_connections", "must be at least 4");
+        }
+        if !(1.0..=86400.0).contains(&beacon.min_period_secs) {
+            return invalid("beacon.min_period_secs", "must be between 1 and 86400");
+        }
+        if !(beacon.min_period_secs..=86400.0).contains(&beacon.max_period_secs) {
+            return invalid(
+                "beacon.max_period_secs",
+                "must be between beacon.min_period_secs and 86400",
+            );
+        }
+        let spreads = [
+            ("beacon.max_jitter", beacon.max_jitter),
+            ("beacon.max_size_jitter", beacon.max_size_jitter),
+        ];
+        for (field, spread) in spreads {
+            if !(spread > 0.0 && spread <= 1.0) {
+                return invalid(field, "must be above 0 and at most 1");
+            }
+        }
+
+        let dns = &self.dns;
+        if !(1..=3600).contains(&dns.window_secs) {
+            return invalid("dns.window_secs", "must be between 1 and 3600");
+        }
+        if !(1..=63).contains(&dns.max_label_len) {
+            return invalid("dns.max_label_len", "must be between 1 and 63");
+        }
+        if !dns.entropy_threshold.is_finite() || dns.entropy_threshold <= 0.0 {
+            return invalid("dns.entropy_threshold", "must be a positive number");
+        }
+        let thresholds = [
+            ("dns.tunnel_threshold", dns.tunnel_threshold),
+            ("dns.txt_threshold", dns.txt_threshold),
+            ("dns.nxdomain_threshold", dns.nxdomain_threshold),
+        ];
+        for (field, threshold) in thresholds {
+            if threshold < 2 {
+                return invalid(field, "must be at least 2");
+            }
+        }
+
+        if !(1..=86400).contains(&self.arp.conflict_window_secs) {
+            return invalid("arp.conflict_window_secs", "must be between 1 and 86400");
+        }
+        if !(1..=3600).contains(&self.arp.flood_window_secs) {
+            return invalid("arp.flood_window_secs", "must be between 1 and 3600");
+        }
+        if self.arp.flood_threshold < 2 {
+            return invalid("arp.flood_threshold", "must be at least 2");
+        }
+
+        if self.storage.retention_days > 36500 {
+            return invalid("storage.retention_days", "must be at most 36500");
+        }
+
+        let dirs = [
+            ("paths.python_dir", &self.paths.python_dir),
+            ("paths.scripts_dir", &self.paths.scripts_dir),
+            ("paths.models_dir", &self.paths.models_dir),
+        ];
+        for (field, dir) in dirs {
+            if let Some(dir) = dir.as_deref().filter(|dir| !dir.is_dir()) {
+                return invalid(field, format!("{} is not a directory", dir.display()));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let config: Config = toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Validates, then replaces the file in one rename so the watcher
+    /// never sees it half written.
+    pub fn save(&self, path: &Path) -> Result<(), ConfigError> {
+        self.validate()?;
+
+        let text = toml::to_string_pretty(self).map_err(ConfigError::Serialize)?;
+        let write = |source| ConfigError::Write {
+            path: path.to_path_buf(),
+            source,
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(write)?;
+        }
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, text).map_err(write)?;
+        fs::rename(&tmp, path).map_err(write)
+    }
+
+    /// Days of history to keep, the environment overriding the file.
+    pub fn retention_days(&self) -> u32 {
+        retention_from_env().unwrap_or(self.storage.retention_days)
+    }
+
+    /// How live captures are opened. Workers restart when these change.
+    pub fn capture_options(&self) -> CaptureOptions {
+        CaptureOptions {
+            bpf_filter: self.capture.bpf_filter.clone(),
+            promiscuous: self.capture.promiscuous,
+            arp: self.arp.enabled,
+        }
+    }
+}
+
+/// Loads `path`, writing the defaults there on first run. A broken file
+/// is reported and ignored rather than keeping monitoring from starting.
+pub fn load_or_create(path: &Path) -> Config {
+    if !path.exists() {
+        let config = Config::default();
+        config.save(path).inspect_err(|e| eprintln!("{e}")).ok();
+        return config;
+    }
+
+    Config::load(path)
+        .inspect_err(|e| eprintln!("{e}. Using defaults."))
+        .unwrap_or_default()
+}
+
+/// Makes `config` current and wakes the monitor loop, which reads interval,
+/// interface rules and thresholds live. Capture changes restart the workers,
+/// a changed models directory (or the bundled one, once the override is
+/// cleared) is loaded right away and the LSTM threshold
+/// override goes to the running detector. Retention applies from the next
+/// prune.
+pub fn apply_config(state: &AppState, config: Config) {
+    state
+        .privacy
+        .write()
+        .unwrap()
+        .set_policy(policy_from_env().unwrap_or(config.privacy.policy));
+    if let Some(store) = state.store.read().unwrap().as_ref() {
+        store.set_retention_days(config.retention_days());
+    }
+
+    let lstm_threshold = config.detection.lstm_threshold;
+    let (restart, models_dir) = {
+        let current = state.config.read().unwrap();
+        let bundled = state.bundled_models_dir.read().unwrap();
+        let models_dir = |config: &Config| {
+            config.paths.models_dir.clone().unwrap_or_else(|| bundled.clone())
+        };
+        let dir = models_dir(&config);
+        (
+            current.capture_options() != config.capture_options(),
+            (dir != models_dir(&current)).then_some(dir),
+        )
+    };
+    *state.config.write().unwrap() = config;
+
+    if let Some(dir) = models_dir {
+        let detector = Detector::load(&dir, lstm_threshold);
+        *state.detector.write().unwrap() = Some(Arc::new(detector));
+    }
+    if let Some(detector) = state.detector.read().unwrap().as_ref() {
+        detector.set_lstm_threshold(lstm_threshold);
+    }
+
+    // The monitor loop starts them again on its next cycle
+    if restart {
+        stop_workers(state);
+    }
+    state.monitor_wake.notify();
+}
+
+/// Reloads the config whenever its file changes. Invalid edits are
+/// reported and the running config is kept.
+pub fn watch_config(state: Arc<AppState>, path: PathBuf) -> Result<(), String> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).map_err(|e| e.to_string())?;
+
+    // Watch the directory: saving through a rename replaces the file's inode
+    let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {e}", dir.display()))?;
+
+    thread::spawn(move || {
+        // Dropping the watcher would stop the events
+        let _watcher = watcher;
+
+        while let Ok(event) = rx.recv() {
+            let touched = |event: &notify::Result<notify::Event>| {
+                event
+                    .as_ref()
+                    .is_ok_and(|e| e.paths.iter().any(|p| p.file_name() == path.file_name()))
+            };
+            if !touched(&event) {
+                continue;
+            }
+            while rx.recv_timeout(RELOAD_DEBOUNCE).is_ok() {}
+
+            if !path.exists() {
+                continue;
+            }
+
+            match Config::load(&path) {
+                Ok(config) if config == *state.config.read().unwrap() => {}
+                Ok(config) => {
+                    eprintln!("config: reloaded {}", path.display());
+                    apply_config(&state, config);
+                }
+                Err(e) => eprintln!("config: {e}. Keeping the previous settings."),
+            }
+        }
+    });
+
+    Ok(())
+}
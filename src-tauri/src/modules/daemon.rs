@@ -0,0 +1,243 @@
+use crate::modules::alerts::MAX_ALERTS;
+use crate::modules::dns::MAX_DNS_RECORDS;
+use crate::modules::monitoring::{control_interface, MonitorAction};
+use crate::modules::notifier::Notifier;
+use crate::modules::privacy::Redact;
+use crate::modules::protocol::WorkerMessage;
+use crate::modules::supervisor::{stop_workers, worker_health};
+use crate::modules::deltas::NetworkDelta;
+use crate::{current_privacy, AppState};
+
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
+#[cfg(unix)]
+use std::fs;
+#[cfg(not(unix))]
+use std::net::{SocketAddr, TcpListener};
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+// In a directory next to the database
+#[cfg(unix)]
+pub const CONTROL_DIR: &str = "control";
+#[cfg(unix)]
+pub const CONTROL_SOCKET: &str = "sentinelai.sock";
+#[cfg(not(unix))]
+pub const DEFAULT_CONTROL_ADDR: &str = "127.0.0.1:7787";
+
+// ---- One JSON object per stdout line ----
+fn log(event: &str, data: Value) {
+    println!("{}", json!({ "ts": Utc::now(), "event": event, "data": data }));
+}
+
+pub struct StdoutNotifier {
+    state: Arc<AppState>,
+}
+
+impl StdoutNotifier {
+    pub fn new(state: Arc<AppState>) -> Self {
+        Self { state }
+    }
+}
+
+impl Notifier for StdoutNotifier {
+    fn network_delta(&self, delta: &NetworkDelta) {
+        let delta = delta.clone().redact(&current_privacy(&self.state));
+        log("network_delta", json!(delta));
+    }
+
+    fn worker_message(&self, message: WorkerMessage) {
+        let event = match &message {
+            WorkerMessage::Verdict(verdict) => {
+                if verdict.is_anomaly {
+                    "anomaly"
+                } else {
+                    "verdict"
+                }
+            }
+            WorkerMessage::Heartbeat(_) => "heartbeat",
+            WorkerMessage::Error(_) => "worker_error",
+            WorkerMessage::Stats(_) => "worker_stats",
+            WorkerMessage::Dns(_) => "dns",
+            WorkerMessage::Arp(_) => "arp",
+        };
+
+        log(event, json!(message.redact(&current_privacy(&self.state))));
+    }
+}
+
+fn handle_client<S>(stream: S, state: &AppState)
+where
+    for<'a> &'a S: Read + Write,
+{
+    let reader = BufReader::new(&stream);
+    let mut writer = &stream;
+
+    for line in reader.lines().map_while(Result::ok) {
+        let line = line.trim();
+        let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+
+        let reply = match command {
+            "" => continue,
+            "start" | "stop" | "pause" | "resume" => {
+                let action = match command {
+                    "start" => MonitorAction::Start,
+                    "stop" => MonitorAction::Stop,
+                    "pause" => MonitorAction::Pause,
+                    _ => MonitorAction::Resume,
+                };
+                match control_interface(state, arg.trim(), action) {
+                    Ok(mode) => json!({ "ok": true, "data": mode }),
+                    Err(e) => json!({ "ok": false, "error": e }),
+                }
+            }
+            "ping" => json!({ "ok": true, "data": "pong" }),
+            "networks" => {
+                let networks = state.networks.read().unwrap().clone();
+                json!({ "ok": true, "data": networks.redact(&current_privacy(state)) })
+            }
+            "workers" => json!({ "ok": true, "data": worker_health(state) }),
+            "pipeline" => json!({ "ok": true, "data": state.pipeline.stats() }),
+            "config" => json!({ "ok": true, "data": *state.config.read().unwrap() }),
+            "wildcard" => {
+                let sockets = state.wildcard_sockets.read().unwrap().clone();
+                json!({ "ok": true, "data": sockets.redact(&current_privacy(state)) })
+            }
+            "processes" => {
+                let processes = state.processes.read().unwrap().clone();
+                json!({ "ok": true, "data": processes.redact(&current_privacy(state)) })
+            }
+            "alerts" => {
+                let alerts = state.alerts.list(None, MAX_ALERTS);
+                json!({ "ok": true, "data": alerts.redact(&current_privacy(state)) })
+            }
+            "dns" => {
+                let records = state.dns_log.list(None, MAX_DNS_RECORDS);
+                json!({ "ok": true, "data": records.redact(&current_privacy(state)) })
+            }
+            "arp" => {
+                let bindings = state.arp.bindings(None);
+                json!({ "ok": true, "data": bindings.redact(&current_privacy(state)) })
+            }
+            "shutdown" => {
+                stop_workers(state);
+                let _ = writeln!(writer, "{}", json!({ "ok": true, "data": "bye" }));
+                log("shutdown", Value::Null);
+                std::process::exit(0);
+            }
+            other => json!({ "ok": false, "error": format!("unknown command '{other}'") }),
+        };
+
+        if writeln!(writer, "{reply}").is_err() {
+            break;
+        }
+    }
+}
+
+/// Where the control socket listens unless `--control` says otherwise.
+pub fn default_control_addr(db_path: &Path) -> String {
+    #[cfg(unix)]
+    return db_path
+        .with_file_name(CONTROL_DIR)
+        .join(CONTROL_SOCKET)
+        .display()
+        .to_string();
+
+    #[cfg(not(unix))]
+    {
+        let _ = db_path;
+        DEFAULT_CONTROL_ADDR.to_string()
+    }
+}
+
+/// A Unix socket only the daemon's user can connect to: it is bound inside
+/// a directory no one else may enter, so it is never reachable by others,
+/// whatever the umask. A missing directory is created that way, an existing
+/// one must already be private. A socket left behind by an unclean exit is
+/// replaced, a live one is not.
+#[cfg(unix)]
+fn bind(addr: &str) -> Result<UnixListener, String> {
+    let path = Path::new(addr);
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    if !dir.exists() {
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(dir)
+            .map_err(|e| format!("Failed to create {}: {e}", dir.display()))?;
+    }
+    let mode = fs::metadata(dir)
+        .map_err(|e| format!("Failed to inspect {}: {e}", dir.display()))?
+        .permissions()
+        .mode();
+    if mode & 0o077 != 0 {
+        return Err(format!(
+            "{} is open to other users, the control socket needs a private (0700) directory",
+            dir.display()
+        ));
+    }
+
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(format!("Control socket {addr} is already in use"));
+        }
+        fs::remove_file(path).map_err(|e| format!("Failed to remove stale {addr}: {e}"))?;
+    }
+
+    UnixListener::bind(path).map_err(|e| format!("Failed to bind control socket {addr}: {e}"))
+}
+
+/// Without Unix sockets, a loopback port. It has no authentication.
+#[cfg(not(unix))]
+fn bind(addr: &str) -> Result<TcpListener, String> {
+    let parsed: SocketAddr = addr
+        .parse()
+        .map_err(|_| format!("Invalid control address '{addr}'"))?;
+
+    if !parsed.ip().is_loopback() {
+        return Err(format!("Control socket must bind to loopback, got {addr}"));
+    }
+
+    TcpListener::bind(parsed).map_err(|e| format!("Failed to bind control socket {addr}: {e}"))
+}
+
+fn accept<S>(incoming: impl Iterator<Item = io::Result<S>>, state: Arc<AppState>)
+where
+    S: Send + 'static,
+    for<'a> &'a S: Read + Write,
+{
+    for stream in incoming {
+        match stream {
+            Ok(stream) => {
+                let state = Arc::clone(&state);
+                thread::spawn(move || handle_client(stream, &state));
+            }
+            Err(e) => eprintln!("control socket: {e}"),
+        }
+    }
+}
+
+/// Line-based control socket: `ping`, `networks`, `workers`, `pipeline`, `config`,
+/// `wildcard`, `processes`, `alerts`, `dns`, `arp`, `shutdown`, and
+/// `start|stop|pause|resume <interface id>`.
+/// `addr` is a socket path in a private directory on Unix, a loopback
+/// `host:port` elsewhere.
+pub fn serve_control(addr: &str, state: Arc<AppState>) -> Result<(), String> {
+    let listener = bind(addr)?;
+
+    log("control_listening", json!({ "addr": addr }));
+    accept(listener.incoming(), state);
+
+    Ok(())
+}
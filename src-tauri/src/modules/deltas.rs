@@ -0,0 +1,138 @@
+use crate::modules::monitoring::Wakeup;
+use crate::modules::notifier::Notifier;
+use crate::{AppState, DefaultStruct, NetworkInfo};
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// At most one delta per interval; changes in between are coalesced
+pub const MIN_DELTA_INTERVAL: Duration = Duration::from_millis(250);
+// Re-check now and then even if nobody marked a change
+const IDLE_CHECK: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum NetworkChange {
+    Add { network: NetworkInfo },
+    Update { network: NetworkInfo },
+    Remove { id: String },
+}
+
+// `seq` grows by one per delta; a gap means one was missed and the
+// frontend should resync with `get_snapshot`
+DefaultStruct! {
+    pub struct NetworkDelta {
+        seq: u64,
+        changes: Vec<NetworkChange>,
+        // Interface ids in display order, only when it changed
+        order: Option<Vec<String>>,
+    }
+}
+
+// The interfaces as of delta `seq`
+DefaultStruct! {
+    pub struct NetworkSnapshot {
+        seq: u64,
+        networks: Vec<NetworkInfo>,
+    }
+}
+
+#[derive(Debug, Default)]
+struct TrackerInner {
+    seq: u64,
+    sent: Vec<NetworkInfo>,
+    // Serialized form of `sent`, for cheap comparison
+    sent_values: HashMap<String, Value>,
+}
+
+/// Remembers what the frontend last received and turns the current
+/// interface list into add/update/remove changes against it.
+#[derive(Debug, Default)]
+pub struct ChangeTracker {
+    inner: Mutex<TrackerInner>,
+    dirty: Wakeup,
+}
+
+impl ChangeTracker {
+    /// Asks the publisher to diff soon.
+    pub fn mark(&self) {
+        self.dirty.notify();
+    }
+
+    /// Diffs `networks` against the last delta. `None` when nothing changed.
+    pub fn diff(&self, networks: &[NetworkInfo]) -> Option<NetworkDelta> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let mut changes = Vec::new();
+        let mut values = HashMap::with_capacity(networks.len());
+
+        for net in networks {
+            let value = serde_json::to_value(net).unwrap_or(Value::Null);
+
+            match inner.sent_values.get(&net.id) {
+                None => changes.push(NetworkChange::Add {
+                    network: net.clone(),
+                }),
+                Some(old) if *old != value => changes.push(NetworkChange::Update {
+                    network: net.clone(),
+                }),
+                Some(_) => {}
+            }
+            values.insert(net.id.clone(), value);
+        }
+
+        for old in &inner.sent {
+            if !values.contains_key(&old.id) {
+                changes.push(NetworkChange::Remove { id: old.id.clone() });
+            }
+        }
+
+        let order: Vec<String> = networks.iter().map(|net| net.id.clone()).collect();
+        let order_changed = !order.iter().eq(inner.sent.iter().map(|net| &net.id));
+
+        if changes.is_empty() && !order_changed {
+            return None;
+        }
+
+        inner.seq += 1;
+        inner.sent = networks.to_vec();
+        inner.sent_values = values;
+
+        Some(NetworkDelta {
+            seq: inner.seq,
+            changes,
+            order: order_changed.then_some(order),
+        })
+    }
+
+    pub fn snapshot(&self) -> NetworkSnapshot {
+        let inner = self.inner.lock().unwrap();
+
+        NetworkSnapshot {
+            seq: inner.seq,
+            networks: inner.sent.clone(),
+        }
+    }
+}
+
+/// Publishes `state.networks` changes as deltas, throttled to
+/// one per `MIN_DELTA_INTERVAL`.
+pub fn start_publisher(notifier: Arc<dyn Notifier>, state: Arc<AppState>) {
+    thread::spawn(move || loop {
+        state.network_changes.dirty.wait(IDLE_CHECK);
+
+        let delta = {
+            let networks = state.networks.read().unwrap();
+            state.network_changes.diff(&networks)
+        };
+        if let Some(delta) = delta {
+            notifier.network_delta(&delta);
+        }
+
+        thread::sleep(MIN_DELTA_INTERVAL);
+    });
+}
@@ -0,0 +1,116 @@
+use crate::modules::flows::Flow;
+use crate::modules::lstm::LstmModel;
+use crate::modules::protocol::{FlowTuple, ModelSource, Verdict};
+use crate::modules::xgboost::XgbModel;
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::path::Path;
+use std::sync::RwLock;
+
+fn flow_tuple(flow: &Flow) -> FlowTuple {
+    FlowTuple {
+        src_ip: flow.src_ip.to_string(),
+        src_port: flow.src_port,
+        dst_ip: flow.dst_ip.to_string(),
+        dst_port: flow.dst_port,
+        protocol: flow.protocol,
+    }
+}
+
+pub struct Detector {
+    xgb: Option<XgbModel>,
+    lstm: Option<LstmModel>,
+    // `detection.lstm_threshold`, kept current by the config
+    lstm_threshold: RwLock<Option<f64>>,
+}
+
+impl fmt::Debug for Detector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Detector")
+            .field("xgb", &self.xgb.is_some())
+            .field("lstm", &self.lstm.is_some())
+            .field("lstm_threshold", &self.lstm_threshold)
+            .finish()
+    }
+}
+
+impl Detector {
+    /// Loads whatever models are available under `models/`. A missing or broken
+    /// model is reported and skipped. `lstm_threshold` overrides the LSTM's own.
+    pub fn load(models_dir: &Path, lstm_threshold: Option<f64>) -> Self {
+        let xgb = XgbModel::load(&models_dir.join("XGBoost"))
+            .inspect_err(|e| eprintln!("XGBoost model unavailable: {e}"))
+            .ok();
+
+        let lstm = LstmModel::load(&models_dir.join("LSTM"))
+            .inspect_err(|e| eprintln!("LSTM model unavailable: {e}"))
+            .ok();
+
+        Self {
+            xgb,
+            lstm,
+            lstm_threshold: RwLock::new(lstm_threshold),
+        }
+    }
+
+    /// Replaces the LSTM threshold override, `None` restores the shipped one.
+    pub fn set_lstm_threshold(&self, threshold: Option<f64>) {
+        *self.lstm_threshold.write().unwrap() = threshold;
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.xgb.is_some() || self.lstm.is_some()
+    }
+
+    /// XGBoost first, LSTM as a fallback when the classifier calls the flow benign.
+    /// Verdicts are stamped with the flow's last packet time.
+    pub fn classify(&self, iface: &str, flow: &Flow) -> Option<Verdict> {
+        let timestamp = DateTime::from_timestamp_micros(flow.last_seen_us).unwrap_or_else(Utc::now);
+        let flow_start = DateTime::from_timestamp_micros(flow.first_seen_us);
+        let tls = flow.tls().map(Box::new);
+
+        let verdict = |model: ModelSource, label: String, is_anomaly: bool, score: f64| Verdict {
+            iface: iface.to_string(),
+            flow: flow_tuple(flow),
+            label,
+            is_anomaly,
+            score,
+            model: Some(model),
+            flow_start,
+            fwd_flags: flow.fwd_flags(),
+            bytes: Some(flow.fwd_bytes() + flow.bwd_bytes()),
+            bwd_packets: Some(flow.bwd_packets()),
+            period_secs: None,
+            domains: Vec::new(),
+            macs: Vec::new(),
+            tls: tls.clone(),
+            timestamp,
+        };
+
+        let mut out = self.xgb.as_ref().map(|xgb| {
+            let xgb_out = xgb.classify(&flow.xgb_features());
+            verdict(ModelSource::Xgb, xgb_out.label, xgb_out.is_anomaly, xgb_out.probability)
+        });
+
+        if out.as_ref().is_none_or(|v| !v.is_anomaly) {
+            if let Some(lstm) = &self.lstm {
+                let threshold = *self.lstm_threshold.read().unwrap();
+                match lstm.score(&flow.lstm_features(), threshold) {
+                    Ok(lstm_out) if lstm_out.is_anomaly || out.is_none() => {
+                        out = Some(verdict(
+                            ModelSource::Lstm,
+                            lstm_out.label().to_string(),
+                            lstm_out.is_anomaly,
+                            lstm_out.error,
+                        ));
+                    }
+                    Ok(_) => {}
+                    Err(e) => eprintln!("{e}"),
+                }
+            }
+        }
+
+        out
+    }
+}
@@ -0,0 +1,654 @@
+use crate::modules::config::DnsConfig;
+use crate::modules::packets::PROTO_UDP;
+use crate::modules::protocol::{DnsPacket, FlowTuple, ModelSource, Verdict};
+use crate::DefaultStruct;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::Mutex;
+
+// Oldest records are dropped once the log is full
+pub const MAX_DNS_RECORDS: usize = 2000;
+
+// ---- Decoder limits, a hostile packet can claim anything ----
+const HEADER_LEN: usize = 12;
+const MAX_QUESTIONS: usize = 8;
+const MAX_ANSWERS: usize = 32;
+const MAX_POINTERS: usize = 16;
+const MAX_TXT_LEN: usize = 100;
+
+// ---- Record types and response codes ----
+const TYPE_A: u16 = 1;
+const TYPE_NS: u16 = 2;
+const TYPE_CNAME: u16 = 5;
+const TYPE_NULL: u16 = 10;
+const TYPE_PTR: u16 = 12;
+const TYPE_MX: u16 = 15;
+const TYPE_TXT: u16 = 16;
+const TYPE_AAAA: u16 = 28;
+const RCODE_NXDOMAIN: u8 = 3;
+
+// Bounds memory under a flood of spoofed clients
+const MAX_CLIENTS: usize = 4096;
+const MAX_OBSERVATIONS_PER_CLIENT: usize = 4096;
+
+// Shorter labels say too little for their entropy to mean anything
+const MIN_ENTROPY_LEN: usize = 16;
+const MAX_DOMAINS_PER_ALERT: usize = 10;
+
+// Stands in for the domain of an alert that spans many domains
+const MANY_DOMAINS: &str = "*";
+
+// One decoded DNS message, by its first question
+DefaultStruct! {
+    pub struct DnsRecord {
+        iface: String,
+        timestamp: DateTime<Utc>,
+        client: String,
+        server: String,
+        id: u16,
+        is_response: bool,
+        name: String,
+        record_type: String,
+        // Responses only
+        rcode: Option<String>,
+        answers: Vec<String>,
+    }
+}
+
+// ---------------------------------------------------------------------
+// DECODER
+// ---------------------------------------------------------------------
+struct Question {
+    name: String,
+    qtype: u16,
+}
+
+struct Message {
+    id: u16,
+    is_response: bool,
+    rcode: u8,
+    questions: Vec<Question>,
+    answers: Vec<String>,
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(at)?, *data.get(at + 1)?]))
+}
+
+/// Reads a possibly compressed name. Returns it lowercased, without the
+/// trailing dot, with the offset right after it in the record.
+fn read_name(msg: &[u8], mut at: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut pointers = 0;
+
+    loop {
+        let len = *msg.get(at)? as usize;
+        match len {
+            0 => {
+                end.get_or_insert(at + 1);
+                break;
+            }
+            // Compression pointer, the name continues elsewhere
+            len if len & 0xC0 == 0xC0 => {
+                pointers += 1;
+                if pointers > MAX_POINTERS {
+                    return None;
+                }
+                end.get_or_insert(at + 2);
+                at = (u16_at(msg, at)? & 0x3FFF) as usize;
+            }
+            len if len & 0xC0 != 0 => return None,
+            len => {
+                let label = msg.get(at + 1..at + 1 + len)?;
+                labels.push(String::from_utf8_lossy(label).to_lowercase());
+                at += 1 + len;
+            }
+        }
+    }
+
+    Some((labels.join("."), end?))
+}
+
+/// Answer data as text: addresses, names, or the TXT strings.
+fn read_rdata(msg: &[u8], rtype: u16, at: usize, len: usize) -> Option<String> {
+    let data = msg.get(at..at + len)?;
+
+    let text = match rtype {
+        TYPE_A => Ipv4Addr::from(<[u8; 4]>::try_from(data).ok()?).to_string(),
+        TYPE_AAAA => Ipv6Addr::from(<[u8; 16]>::try_from(data).ok()?).to_string(),
+        TYPE_NS | TYPE_CNAME | TYPE_PTR => read_name(msg, at)?.0,
+        TYPE_MX => read_name(msg, at + 2)?.0,
+        TYPE_TXT => {
+            let mut text = String::new();
+            let mut rest = data;
+            while let Some((&n, tail)) = rest.split_first() {
+                let chunk = tail.get(..n as usize)?;
+                text.push_str(&String::from_utf8_lossy(chunk));
+                rest = &tail[n as usize..];
+            }
+            text.chars().take(MAX_TXT_LEN).collect()
+        }
+        _ => format!("{} ({len} bytes)", type_name(rtype)),
+    };
+
+    Some(text)
+}
+
+/// Parses a DNS message. A truncated answer section keeps what came before.
+fn parse_message(msg: &[u8]) -> Option<Message> {
+    if msg.len() < HEADER_LEN {
+        return None;
+    }
+
+    let flags = u16_at(msg, 2)?;
+    let question_count = u16_at(msg, 4)? as usize;
+    let answer_count = u16_at(msg, 6)? as usize;
+
+    let mut message = Message {
+        id: u16_at(msg, 0)?,
+        is_response: flags & 0x8000 != 0,
+        rcode: (flags & 0x000F) as u8,
+        questions: Vec::new(),
+        answers: Vec::new(),
+    };
+
+    // ---- Questions ----
+    let mut at = HEADER_LEN;
+    for _ in 0..question_count.min(MAX_QUESTIONS) {
+        let (name, next) = read_name(msg, at)?;
+        message.questions.push(Question {
+            name,
+            qtype: u16_at(msg, next)?,
+        });
+        at = next + 4;
+    }
+    if question_count > MAX_QUESTIONS {
+        return Some(message);
+    }
+
+    // ---- Answers ----
+    for _ in 0..answer_count.min(MAX_ANSWERS) {
+        let Some((_, next)) = read_name(msg, at) else {
+            break;
+        };
+        let (Some(rtype), Some(len)) = (u16_at(msg, next), u16_at(msg, next + 8)) else {
+            break;
+        };
+        let start = next + 10;
+        let Some(data) = read_rdata(msg, rtype, start, len as usize) else {
+            break;
+        };
+        message.answers.push(data);
+        at = start + len as usize;
+    }
+
+    Some(message)
+}
+
+fn type_name(rtype: u16) -> String {
+    let name = match rtype {
+        TYPE_A => "A",
+        TYPE_NS => "NS",
+        TYPE_CNAME => "CNAME",
+        6 => "SOA",
+        TYPE_NULL => "NULL",
+        TYPE_PTR => "PTR",
+        TYPE_MX => "MX",
+        TYPE_TXT => "TXT",
+        TYPE_AAAA => "AAAA",
+        33 => "SRV",
+        64 => "SVCB",
+        65 => "HTTPS",
+        255 => "ANY",
+        // RFC 3597 notation for the rest
+        other => return format!("TYPE{other}"),
+    };
+    name.into()
+}
+
+fn rcode_name(rcode: u8) -> String {
+    let name = match rcode {
+        0 => "NOERROR",
+        1 => "FORMERR",
+        2 => "SERVFAIL",
+        RCODE_NXDOMAIN => "NXDOMAIN",
+        4 => "NOTIMP",
+        5 => "REFUSED",
+        other => return format!("RCODE{other}"),
+    };
+    name.into()
+}
+
+/// Decodes a packet a capture forwarded. `None` for anything that isn't
+/// a well-formed DNS message with at least one question.
+pub fn decode(packet: &DnsPacket) -> Option<DnsRecord> {
+    let message = parse_message(&packet.payload)?;
+    let question = message.questions.into_iter().next()?;
+
+    let (client, server) = match message.is_response {
+        true => (&packet.dst_ip, &packet.src_ip),
+        false => (&packet.src_ip, &packet.dst_ip),
+    };
+
+    Some(DnsRecord {
+        iface: packet.iface.clone(),
+        timestamp: packet.timestamp,
+        client: client.clone(),
+        server: server.clone(),
+        id: message.id,
+        is_response: message.is_response,
+        name: question.name,
+        record_type: type_name(question.qtype),
+        rcode: message.is_response.then(|| rcode_name(message.rcode)),
+        answers: message.answers,
+    })
+}
+
+// ---------------------------------------------------------------------
+// LOG
+// ---------------------------------------------------------------------
+/// Bounded, newest-last list of DNS messages across all interfaces.
+#[derive(Debug, Default)]
+pub struct DnsLog {
+    records: Mutex<VecDeque<DnsRecord>>,
+}
+
+impl DnsLog {
+    pub fn record(&self, record: DnsRecord) {
+        let mut records = self.records.lock().unwrap();
+        if records.len() == MAX_DNS_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /// Newest first, optionally limited to one interface.
+    pub fn list(&self, iface: Option<&str>, limit: usize) -> Vec<DnsRecord> {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|r| iface.is_none_or(|name| r.iface == name))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+// ---------------------------------------------------------------------
+// TUNNELING / DGA DETECTION
+// ---------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Signal {
+    // Query for a long or random-looking subdomain
+    OddName,
+    // TXT or NULL query, the usual carriers of tunneled data
+    TxtQuery,
+    // Name that doesn't exist, what a DGA mostly hits
+    NxDomain,
+}
+
+impl Signal {
+    fn label(self) -> &'static str {
+        match self {
+            Signal::OddName => "DNS_TUNNELING",
+            Signal::TxtQuery => "DNS_TXT_FLOOD",
+            Signal::NxDomain => "DGA",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Observation {
+    at: DateTime<Utc>,
+    signal: Signal,
+    domain: String,
+    name: String,
+}
+
+#[derive(Debug, Default)]
+struct Client {
+    observations: VecDeque<Observation>,
+    // (signal, domain) → when it was last alerted, to alert once per window
+    alerted: HashMap<(Signal, String), DateTime<Utc>>,
+    server: String,
+}
+
+impl Client {
+    fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.observations.back().map(|o| o.at)
+    }
+}
+
+/// Splits a name into subdomain and registered domain. Without a public
+/// suffix list, the domain is the last two labels, or three under short
+/// country suffixes like `co.uk`.
+fn split_domain(name: &str) -> (&str, &str) {
+    let labels: Vec<&str> = name.split('.').collect();
+    let n = labels.len();
+    let keep = if n >= 3 && labels[n - 1].len() == 2 && labels[n - 2].len() <= 3 {
+        3
+    } else {
+        2
+    };
+    if n <= keep {
+        return ("", name);
+    }
+
+    let cut: usize = labels[..n - keep].iter().map(|l| l.len() + 1).sum();
+    (&name[..cut - 1], &name[cut..])
+}
+
+/// Shannon entropy in bits per character.
+fn entropy(text: &str) -> f64 {
+    let mut counts = [0usize; 256];
+    for b in text.bytes() {
+        counts[b as usize] += 1;
+    }
+
+    let len = text.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// A label that looks like encoded data: too long, or long and random.
+/// Hex and base32 mix letters and digits without hyphens; words glued
+/// together, like `googleusercontent`, reach the same entropy but don't.
+fn is_odd_label(label: &str, config: &DnsConfig) -> bool {
+    if label.len() > config.max_label_len {
+        return true;
+    }
+
+    label.len() >= MIN_ENTROPY_LEN
+        && label.bytes().all(|b| b.is_ascii_alphanumeric())
+        && label.bytes().any(|b| b.is_ascii_digit())
+        && label.bytes().any(|b| b.is_ascii_alphabetic())
+        && entropy(label) >= config.entropy_threshold
+}
+
+/// Cross-message detector: per client, keeps the suspicious DNS traffic of
+/// the last `window_secs` and alerts when one kind of it piles up.
+#[derive(Debug, Default)]
+pub struct DnsDetector {
+    clients: Mutex<HashMap<String, Client>>,
+}
+
+impl DnsDetector {
+    /// Feeds one decoded message. Returns an anomalous verdict per signal
+    /// that crossed its threshold, with the offending names.
+    pub fn observe(&self, record: &DnsRecord, config: &DnsConfig) -> Vec<Verdict> {
+        // Reverse lookups are long and hex-heavy by design
+        if !config.enabled || record.name.ends_with(".arpa") {
+            return Vec::new();
+        }
+
+        let (subdomain, domain) = split_domain(&record.name);
+        let mut signals = Vec::new();
+        if record.is_response {
+            if record.rcode.as_deref() == Some("NXDOMAIN") {
+                signals.push(Signal::NxDomain);
+            }
+        } else {
+            if subdomain.split('.').any(|l| is_odd_label(l, config)) {
+                signals.push(Signal::OddName);
+            }
+            if matches!(record.record_type.as_str(), "TXT" | "NULL") {
+                signals.push(Signal::TxtQuery);
+            }
+        }
+        if signals.is_empty() {
+            return Vec::new();
+        }
+
+        let now = record.timestamp;
+        let window = Duration::seconds(config.window_secs as i64);
+        let mut clients = self.clients.lock().unwrap();
+
+        if !clients.contains_key(&record.client) {
+            evict(&mut clients, now - window);
+        }
+        let client = clients.entry(record.client.clone()).or_default();
+        client.server = record.server.clone();
+
+        // ---- Slide the window ----
+        for &signal in &signals {
+            client.observations.push_back(Observation {
+                at: now,
+                signal,
+                domain: domain.to_string(),
+                name: record.name.clone(),
+            });
+        }
+        while client.observations.len() > MAX_OBSERVATIONS_PER_CLIENT
+            || client
+                .observations
+                .front()
+                .is_some_and(|o| o.at < now - window)
+        {
+            client.observations.pop_front();
+        }
+        client.alerted.retain(|_, at| *at >= now - window);
+
+        let mut alerts = Vec::new();
+
+        for signal in signals {
+            // DGA names spread over many domains, the others stay on one
+            let target = match signal {
+                Signal::NxDomain => MANY_DOMAINS,
+                _ => domain,
+            };
+            let matching: Vec<&Observation> = client
+                .observations
+                .iter()
+                .filter(|o| o.signal == signal && (target == MANY_DOMAINS || o.domain == target))
+                .collect();
+
+            let mut names: Vec<&str> = Vec::new();
+            let mut seen = HashSet::new();
+            for o in matching.iter().rev() {
+                if seen.insert(o.name.as_str()) {
+                    names.push(&o.name);
+                }
+            }
+
+            let (count, threshold) = match signal {
+                Signal::OddName => (names.len(), config.tunnel_threshold),
+                Signal::TxtQuery => (matching.len(), config.txt_threshold),
+                Signal::NxDomain => (names.len(), config.nxdomain_threshold),
+            };
+            if count < threshold {
+                continue;
+            }
+
+            let key = (signal, target.to_string());
+            if client.alerted.contains_key(&key) {
+                continue;
+            }
+            client.alerted.insert(key, now);
+
+            alerts.push(Verdict {
+                iface: record.iface.clone(),
+                flow: FlowTuple {
+                    src_ip: record.client.clone(),
+                    src_port: 0,
+                    dst_ip: client.server.clone(),
+                    dst_port: 53,
+                    protocol: PROTO_UDP,
+                },
+                label: signal.label().into(),
+                is_anomaly: true,
+                score: count as f64,
+                model: Some(ModelSource::Dns),
+                flow_start: matching.first().map(|o| o.at),
+                fwd_flags: None,
+                bytes: None,
+                bwd_packets: None,
+                period_secs: None,
+                domains: names
+                    .iter()
+                    .take(MAX_DOMAINS_PER_ALERT)
+                    .map(|n| n.to_string())
+                    .collect(),
+                macs: Vec::new(),
+                tls: None,
+                timestamp: now,
+            });
+        }
+
+        alerts
+    }
+}
+
+/// Makes room for a new client: drops idle ones, then the least recent.
+fn evict(clients: &mut HashMap<String, Client>, cutoff: DateTime<Utc>) {
+    if clients.len() < MAX_CLIENTS {
+        return;
+    }
+
+    clients.retain(|_, client| client.last_seen().is_some_and(|at| at >= cutoff));
+
+    if clients.len() >= MAX_CLIENTS {
+        let oldest = clients
+            .iter()
+            .min_by_key(|(_, client)| client.last_seen())
+            .map(|(ip, _)| ip.clone());
+        if let Some(ip) = oldest {
+            clients.remove(&ip);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(name: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        for label in name.split('.') {
+            out.push(label.len() as u8);
+            out.extend(label.as_bytes());
+        }
+        out.push(0);
+        out
+    }
+
+    // A response header and its one question, `qname` type A
+    fn response(qname: &str, answers: u16) -> Vec<u8> {
+        let mut msg = vec![0x12, 0x34, 0x81, 0x80, 0, 1];
+        msg.extend(answers.to_be_bytes());
+        msg.extend([0, 0, 0, 0]);
+        msg.extend(name(qname));
+        msg.extend([0, 1, 0, 1]);
+        msg
+    }
+
+    // An answer named by a pointer to the question
+    fn answer(msg: &mut Vec<u8>, rtype: u16, rdata: &[u8]) {
+        msg.extend([0xC0, HEADER_LEN as u8]);
+        msg.extend(rtype.to_be_bytes());
+        msg.extend([0, 1, 0, 0, 0, 60]);
+        msg.extend((rdata.len() as u16).to_be_bytes());
+        msg.extend(rdata);
+    }
+
+    #[test]
+    fn compression_pointer_loops_are_rejected() {
+        // The question's name points at itself
+        let mut msg = response("example.com", 0);
+        msg.truncate(HEADER_LEN);
+        msg.extend([0xC0, HEADER_LEN as u8, 0, 1, 0, 1]);
+        assert!(read_name(&msg, HEADER_LEN).is_none());
+        assert!(parse_message(&msg).is_none());
+
+        // Two labels pointing at each other
+        let mut msg = response("example.com", 0);
+        msg.truncate(HEADER_LEN);
+        msg.extend([1, b'a', 0xC0, HEADER_LEN as u8]);
+        assert!(read_name(&msg, HEADER_LEN).is_none());
+    }
+
+    #[test]
+    fn pointers_resolve_within_the_limit() {
+        let mut msg = response("www.example.com", 1);
+        answer(&mut msg, TYPE_CNAME, &[0xC0, HEADER_LEN as u8 + 4]);
+
+        let message = parse_message(&msg).unwrap();
+        assert_eq!(message.questions[0].name, "www.example.com");
+        assert_eq!(message.answers, vec!["example.com"]);
+    }
+
+    #[test]
+    fn truncated_answers_keep_the_ones_before() {
+        let mut msg = response("example.com", 3);
+        answer(&mut msg, TYPE_A, &[93, 184, 216, 34]);
+        answer(&mut msg, TYPE_A, &[93, 184, 216, 35]);
+        // The third answer is cut off halfway through its address
+        answer(&mut msg, TYPE_A, &[93, 184, 216, 36]);
+        msg.truncate(msg.len() - 2);
+
+        let message = parse_message(&msg).unwrap();
+        assert_eq!(message.answers, vec!["93.184.216.34", "93.184.216.35"]);
+
+        // An address of the wrong length is as good as truncated
+        let mut msg = response("example.com", 2);
+        answer(&mut msg, TYPE_A, &[93, 184, 216, 34]);
+        answer(&mut msg, TYPE_A, &[93, 184, 216]);
+        assert_eq!(parse_message(&msg).unwrap().answers, vec!["93.184.216.34"]);
+    }
+
+    #[test]
+    fn txt_answers_are_capped() {
+        let mut rdata = Vec::new();
+        for fill in [b'a', b'b'] {
+            rdata.push(255);
+            rdata.extend([fill; 255]);
+        }
+        let mut msg = response("example.com", 1);
+        answer(&mut msg, TYPE_TXT, &rdata);
+
+        let answers = parse_message(&msg).unwrap().answers;
+        assert_eq!(answers, vec!["a".repeat(MAX_TXT_LEN)]);
+
+        // A string longer than the record it is in
+        let mut msg = response("example.com", 1);
+        answer(&mut msg, TYPE_TXT, &[200, b'x', b'y']);
+        assert!(parse_message(&msg).unwrap().answers.is_empty());
+    }
+
+    #[test]
+    fn registered_domain_is_two_labels_or_three_under_short_suffixes() {
+        assert_eq!(split_domain("www.example.com"), ("www", "example.com"));
+        assert_eq!(split_domain("example.com"), ("", "example.com"));
+        assert_eq!(split_domain("a.b.example.co.uk"), ("a.b", "example.co.uk"));
+        assert_eq!(split_domain("example.co.uk"), ("", "example.co.uk"));
+        assert_eq!(split_domain("x.example.com.au"), ("x", "example.com.au"));
+        // A two-letter suffix under a long label is a domain of its own
+        assert_eq!(split_domain("www.example.io"), ("www", "example.io"));
+    }
+
+    #[test]
+    fn glued_words_are_not_odd_but_hex_is() {
+        let config = DnsConfig::default();
+
+        // Both are random enough to pass the entropy threshold
+        assert!(entropy("googleusercontent") >= config.entropy_threshold);
+        assert!(entropy("3f9c2e17b0d48e6a1c5b") >= config.entropy_threshold);
+
+        assert!(!is_odd_label("googleusercontent", &config));
+        assert!(is_odd_label("3f9c2e17b0d48e6a1c5b", &config));
+        // Too short to judge, however random
+        assert!(!is_odd_label("3f9c2e17", &config));
+        assert!(is_odd_label(&"a".repeat(config.max_label_len + 1), &config));
+    }
+}
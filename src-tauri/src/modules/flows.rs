@@ -0,0 +1,812 @@
+use crate::modules::packets::{
+    Packet, PROTO_TCP, TCP_ACK, TCP_CWR, TCP_ECE, TCP_FIN, TCP_PSH, TCP_RST, TCP_SYN, TCP_URG,
+};
+use crate::modules::protocol::TcpFlags;
+use crate::modules::tls::{self, TlsInfo};
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+// ---------------------------------------------------------------------
+// THE EXACT 77 LSTM FEATURES (the CICIDS order the models were trained on)
+// ---------------------------------------------------------------------
+pub const LSTM_FEATURE_COUNT: usize = 77;
+
+// XGBoost = "Destination Port" + the 77 LSTM features
+pub const XGB_FEATURE_COUNT: usize = LSTM_FEATURE_COUNT + 1;
+
+pub const LSTM_FEATURES: [&str; LSTM_FEATURE_COUNT] = [
+    "Flow Duration", "Total Fwd Packets", "Total Backward Packets",
+    "Total Length of Fwd Packets", "Total Length of Bwd Packets",
+    "Fwd Packet Length Max", "Fwd Packet Length Min", "Fwd Packet Length Mean",
+    "Fwd Packet Length Std", "Bwd Packet Length Max", "Bwd Packet Length Min",
+    "Bwd Packet Length Mean", "Bwd Packet Length Std", "Flow Bytes/s",
+    "Flow Packets/s", "Flow IAT Mean", "Flow IAT Std", "Flow IAT Max",
+    "Flow IAT Min", "Fwd IAT Total", "Fwd IAT Mean", "Fwd IAT Std",
+    "Fwd IAT Max", "Fwd IAT Min", "Bwd IAT Total", "Bwd IAT Mean",
+    "Bwd IAT Std", "Bwd IAT Max", "Bwd IAT Min", "Fwd PSH Flags",
+    "Bwd PSH Flags", "Fwd URG Flags", "Bwd URG Flags", "Fwd Header Length",
+    "Bwd Header Length", "Fwd Packets/s", "Bwd Packets/s", "Min Packet Length",
+    "Max Packet Length", "Packet Length Mean", "Packet Length Std",
+    "Packet Length Variance", "FIN Flag Count", "SYN Flag Count",
+    "RST Flag Count", "PSH Flag Count", "ACK Flag Count", "URG Flag Count",
+    "CWE Flag Count", "ECE Flag Count", "Down/Up Ratio", "Average Packet Size",
+    "Avg Fwd Segment Size", "Avg Bwd Segment Size", "Fwd Header Length.1",
+    "Fwd Avg Bytes/Bulk", "Fwd Avg Packets/Bulk", "Fwd Avg Bulk Rate",
+    "Bwd Avg Bytes/Bulk", "Bwd Avg Packets/Bulk", "Bwd Avg Bulk Rate",
+    "Subflow Fwd Packets", "Subflow Fwd Bytes", "Subflow Bwd Packets",
+    "Subflow Bwd Bytes", "Init_Win_bytes_forward", "Init_Win_bytes_backward",
+    "act_data_pkt_fwd", "min_seg_size_forward", "Active Mean", "Active Std",
+    "Active Max", "Active Min", "Idle Mean", "Idle Std", "Idle Max",
+    "Idle Min",
+];
+
+pub fn xgb_feature_names() -> Vec<&'static str> {
+    std::iter::once("Destination Port").chain(LSTM_FEATURES).collect()
+}
+
+// ---- Timeouts (microseconds) ----
+// Flow expiry follows NFStream's defaults, which the Python worker used,
+// active/idle and bulk splitting follow CICFlowMeter, which the models were trained on.
+const IDLE_TIMEOUT_US: i64 = 120_000_000;
+const ACTIVE_TIMEOUT_US: i64 = 1_800_000_000;
+const ACTIVITY_TIMEOUT_US: i64 = 5_000_000;
+const SUBFLOW_GAP_US: i64 = 1_000_000;
+const BULK_GAP_US: i64 = 1_000_000;
+const BULK_MIN_PACKETS: u64 = 4;
+
+// ---- Running summary (Welford, sample std like CICFlowMeter) ----
+#[derive(Debug, Clone, Copy, Default)]
+struct Stats {
+    count: u64,
+    sum: f64,
+    mean: f64,
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Stats {
+    fn add(&mut self, value: f64) {
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+
+        self.count += 1;
+        self.sum += value;
+
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.mean
+        }
+    }
+
+    fn variance(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            self.m2 / (self.count - 1) as f64
+        }
+    }
+
+    fn std(&self) -> f64 {
+        self.variance().sqrt()
+    }
+}
+
+// ---- Bulk transfer tracking (CICFlowMeter semantics) ----
+#[derive(Debug, Clone, Copy, Default)]
+struct Bulk {
+    start: i64,
+    last: i64,
+    helper_packets: u64,
+    helper_bytes: u64,
+    state_count: u64,
+    packets: u64,
+    bytes: u64,
+    duration_us: i64,
+}
+
+impl Bulk {
+    fn update(&mut self, ts: i64, size: u32, other_last: i64) {
+        if other_last > self.start {
+            self.start = 0;
+        }
+        if size == 0 {
+            return;
+        }
+
+        let size = size as u64;
+
+        if self.start == 0 || ts - self.last > BULK_GAP_US {
+            self.start = ts;
+            self.last = ts;
+            self.helper_packets = 1;
+            self.helper_bytes = size;
+            return;
+        }
+
+        self.helper_packets += 1;
+        self.helper_bytes += size;
+
+        if self.helper_packets == BULK_MIN_PACKETS {
+            self.state_count += 1;
+            self.packets += self.helper_packets;
+            self.bytes += self.helper_bytes;
+            self.duration_us += ts - self.start;
+        } else if self.helper_packets > BULK_MIN_PACKETS {
+            self.packets += 1;
+            self.bytes += size;
+            self.duration_us += ts - self.last;
+        }
+
+        self.last = ts;
+    }
+
+    fn avg_bytes(&self) -> f64 {
+        if self.state_count == 0 {
+            0.0
+        } else {
+            self.bytes as f64 / self.state_count as f64
+        }
+    }
+
+    fn avg_packets(&self) -> f64 {
+        if self.state_count == 0 {
+            0.0
+        } else {
+            self.packets as f64 / self.state_count as f64
+        }
+    }
+
+    fn rate(&self) -> f64 {
+        if self.duration_us <= 0 {
+            0.0
+        } else {
+            self.bytes as f64 / (self.duration_us as f64 / 1_000_000.0)
+        }
+    }
+}
+
+// ---- Per-direction counters ----
+#[derive(Debug, Clone)]
+struct Direction {
+    lengths: Stats,
+    iat: Stats,
+    last_ts: Option<i64>,
+    header_bytes: u64,
+    psh: u64,
+    urg: u64,
+    init_win: i64,
+    bulk: Bulk,
+}
+
+impl Default for Direction {
+    fn default() -> Self {
+        Self {
+            lengths: Stats::default(),
+            iat: Stats::default(),
+            last_ts: None,
+            header_bytes: 0,
+            psh: 0,
+            urg: 0,
+            // CICIDS uses -1 when a direction never carried a TCP segment
+            init_win: -1,
+            bulk: Bulk::default(),
+        }
+    }
+}
+
+/// Direction-agnostic 5-tuple: both halves of a conversation map to the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FlowKey {
+    lo_ip: IpAddr,
+    lo_port: u16,
+    hi_ip: IpAddr,
+    hi_port: u16,
+    protocol: u8,
+}
+
+impl FlowKey {
+    pub fn of(packet: &Packet) -> Self {
+        let src = (packet.src_ip, packet.src_port);
+        let dst = (packet.dst_ip, packet.dst_port);
+        let (lo, hi) = if src <= dst { (src, dst) } else { (dst, src) };
+
+        Self {
+            lo_ip: lo.0,
+            lo_port: lo.1,
+            hi_ip: hi.0,
+            hi_port: hi.1,
+            protocol: packet.protocol,
+        }
+    }
+}
+
+/// A bidirectional flow. The first packet seen decides the forward direction.
+#[derive(Debug, Clone)]
+pub struct Flow {
+    pub src_ip: IpAddr,
+    pub src_port: u16,
+    pub dst_ip: IpAddr,
+    pub dst_port: u16,
+    pub protocol: u8,
+    pub first_seen_us: i64,
+    pub last_seen_us: i64,
+
+    fwd: Direction,
+    bwd: Direction,
+    lengths: Stats,
+    flow_iat: Stats,
+
+    fin: u64,
+    syn: u64,
+    rst: u64,
+    psh: u64,
+    ack: u64,
+    urg: u64,
+    cwr: u64,
+    ece: u64,
+    // Forward direction only, for the scan detector
+    fwd_flags: TcpFlags,
+
+    act_data_pkt_fwd: u64,
+    min_seg_size_fwd: Option<u32>,
+
+    start_active: i64,
+    end_active: i64,
+    active: Stats,
+    idle: Stats,
+
+    subflows: u64,
+
+    // Leading TCP payload per direction, None once it isn't TLS
+    client_tls: Option<Vec<u8>>,
+    server_tls: Option<Vec<u8>>,
+}
+
+impl Flow {
+    fn new(packet: &Packet) -> Self {
+        let ts = packet.timestamp_us;
+
+        Self {
+            src_ip: packet.src_ip,
+            src_port: packet.src_port,
+            dst_ip: packet.dst_ip,
+            dst_port: packet.dst_port,
+            protocol: packet.protocol,
+            first_seen_us: ts,
+            last_seen_us: ts,
+            fwd: Direction::default(),
+            bwd: Direction::default(),
+            lengths: Stats::default(),
+            flow_iat: Stats::default(),
+            fin: 0,
+            syn: 0,
+            rst: 0,
+            psh: 0,
+            ack: 0,
+            urg: 0,
+            cwr: 0,
+            ece: 0,
+            fwd_flags: TcpFlags::default(),
+            act_data_pkt_fwd: 0,
+            min_seg_size_fwd: None,
+            start_active: ts,
+            end_active: ts,
+            active: Stats::default(),
+            idle: Stats::default(),
+            subflows: 1,
+            client_tls: Some(Vec::new()),
+            server_tls: Some(Vec::new()),
+        }
+    }
+
+    /// TLS handshake metadata, when the flow opened with a ClientHello.
+    pub fn tls(&self) -> Option<TlsInfo> {
+        tls::inspect(
+            self.client_tls.as_deref()?,
+            self.server_tls.as_deref().unwrap_or_default(),
+        )
+    }
+
+    fn is_forward(&self, packet: &Packet) -> bool {
+        packet.src_ip == self.src_ip && packet.src_port == self.src_port
+    }
+
+    fn add(&mut self, packet: &Packet) {
+        let ts = packet.timestamp_us.max(self.last_seen_us);
+        let size = packet.payload_len;
+        let forward = self.is_forward(packet);
+
+        // ---- Flow-level timing ----
+        if self.lengths.count > 0 {
+            let gap = ts - self.last_seen_us;
+            self.flow_iat.add(gap as f64);
+
+            if gap > SUBFLOW_GAP_US {
+                self.subflows += 1;
+            }
+        }
+
+        if ts - self.end_active > ACTIVITY_TIMEOUT_US {
+            if self.end_active > self.start_active {
+                self.active.add((self.end_active - self.start_active) as f64);
+            }
+            self.idle.add((ts - self.end_active) as f64);
+            self.start_active = ts;
+        }
+        self.end_active = ts;
+
+        // ---- Direction-level counters ----
+        let (dir, other_bulk_last) = if forward {
+            (&mut self.fwd, self.bwd.bulk.last)
+        } else {
+            (&mut self.bwd, self.fwd.bulk.last)
+        };
+
+        if let Some(last) = dir.last_ts {
+            dir.iat.add((ts - last) as f64);
+        }
+        dir.last_ts = Some(ts);
+        dir.lengths.add(size as f64);
+        dir.header_bytes += packet.header_len as u64;
+        dir.bulk.update(ts, size, other_bulk_last);
+
+        if packet.protocol == PROTO_TCP {
+            if dir.init_win < 0 {
+                dir.init_win = packet.tcp_window as i64;
+            }
+            if packet.has_flag(TCP_PSH) {
+                dir.psh += 1;
+            }
+            if packet.has_flag(TCP_URG) {
+                dir.urg += 1;
+            }
+        }
+
+        if packet.protocol == PROTO_TCP && !packet.payload.is_empty() {
+            let buffer = if forward {
+                &mut self.client_tls
+            } else {
+                &mut self.server_tls
+            };
+            tls::collect(buffer, packet.payload);
+        }
+
+        if forward {
+            if size > 0 {
+                self.act_data_pkt_fwd += 1;
+            }
+            self.min_seg_size_fwd = Some(
+                self.min_seg_size_fwd
+                    .map_or(packet.header_len, |min| min.min(packet.header_len)),
+            );
+        }
+
+        // ---- Flag counts over both directions ----
+        if packet.protocol == PROTO_TCP {
+            let counters = [
+                (TCP_FIN, &mut self.fin),
+                (TCP_SYN, &mut self.syn),
+                (TCP_RST, &mut self.rst),
+                (TCP_PSH, &mut self.psh),
+                (TCP_ACK, &mut self.ack),
+                (TCP_URG, &mut self.urg),
+                (TCP_CWR, &mut self.cwr),
+                (TCP_ECE, &mut self.ece),
+            ];
+            for (flag, counter) in counters {
+                if packet.has_flag(flag) {
+                    *counter += 1;
+                }
+            }
+
+            if forward {
+                let fwd = &mut self.fwd_flags;
+                let counters = [
+                    (TCP_SYN, &mut fwd.syn),
+                    (TCP_ACK, &mut fwd.ack),
+                    (TCP_RST, &mut fwd.rst),
+                    (TCP_FIN, &mut fwd.fin),
+                ];
+                for (flag, counter) in counters {
+                    if packet.has_flag(flag) {
+                        *counter += 1;
+                    }
+                }
+            }
+        }
+
+        self.lengths.add(size as f64);
+        self.last_seen_us = ts;
+    }
+
+    fn finish(&mut self) {
+        if self.end_active > self.start_active {
+            self.active.add((self.end_active - self.start_active) as f64);
+            self.start_active = self.end_active;
+        }
+    }
+
+    pub fn duration_us(&self) -> i64 {
+        self.last_seen_us - self.first_seen_us
+    }
+
+    pub fn bwd_packets(&self) -> u64 {
+        self.bwd.lengths.count
+    }
+
+    pub fn fwd_bytes(&self) -> u64 {
+        self.fwd.lengths.sum as u64
+    }
+
+    pub fn bwd_bytes(&self) -> u64 {
+        self.bwd.lengths.sum as u64
+    }
+
+    /// SYN/ACK/RST/FIN counts sent by the initiator, TCP flows only.
+    pub fn fwd_flags(&self) -> Option<TcpFlags> {
+        (self.protocol == PROTO_TCP).then_some(self.fwd_flags)
+    }
+
+    /// The 77-feature LSTM vector, in `LSTM_FEATURES` order.
+    pub fn lstm_features(&self) -> [f64; LSTM_FEATURE_COUNT] {
+        let duration = self.duration_us() as f64;
+        let seconds = duration / 1_000_000.0;
+        let per_second = |value: f64| if seconds > 0.0 { value / seconds } else { 0.0 };
+
+        let fwd = &self.fwd;
+        let bwd = &self.bwd;
+        let fwd_packets = fwd.lengths.count as f64;
+        let bwd_packets = bwd.lengths.count as f64;
+        let subflows = self.subflows.max(1) as f64;
+
+        [
+            duration,
+            fwd_packets,
+            bwd_packets,
+            fwd.lengths.sum,
+            bwd.lengths.sum,
+            fwd.lengths.max,
+            fwd.lengths.min,
+            fwd.lengths.mean(),
+            fwd.lengths.std(),
+            bwd.lengths.max,
+            bwd.lengths.min,
+            bwd.lengths.mean(),
+            bwd.lengths.std(),
+            per_second(self.lengths.sum),
+            per_second(self.lengths.count as f64),
+            self.flow_iat.mean(),
+            self.flow_iat.std(),
+            self.flow_iat.max,
+            self.flow_iat.min,
+            fwd.iat.sum,
+            fwd.iat.mean(),
+            fwd.iat.std(),
+            fwd.iat.max,
+            fwd.iat.min,
+            bwd.iat.sum,
+            bwd.iat.mean(),
+            bwd.iat.std(),
+            bwd.iat.max,
+            bwd.iat.min,
+            fwd.psh as f64,
+            bwd.psh as f64,
+            fwd.urg as f64,
+            bwd.urg as f64,
+            fwd.header_bytes as f64,
+            bwd.header_bytes as f64,
+            per_second(fwd_packets),
+            per_second(bwd_packets),
+            self.lengths.min,
+            self.lengths.max,
+            self.lengths.mean(),
+            self.lengths.std(),
+            self.lengths.variance(),
+            self.fin as f64,
+            self.syn as f64,
+            self.rst as f64,
+            self.psh as f64,
+            self.ack as f64,
+            self.urg as f64,
+            self.cwr as f64,
+            self.ece as f64,
+            if fwd_packets > 0.0 { bwd_packets / fwd_packets } else { 0.0 },
+            self.lengths.mean(),
+            fwd.lengths.mean(),
+            bwd.lengths.mean(),
+            fwd.header_bytes as f64,
+            fwd.bulk.avg_bytes(),
+            fwd.bulk.avg_packets(),
+            fwd.bulk.rate(),
+            bwd.bulk.avg_bytes(),
+            bwd.bulk.avg_packets(),
+            bwd.bulk.rate(),
+            fwd_packets / subflows,
+            fwd.lengths.sum / subflows,
+            bwd_packets / subflows,
+            bwd.lengths.sum / subflows,
+            fwd.init_win as f64,
+            bwd.init_win as f64,
+            self.act_data_pkt_fwd as f64,
+            self.min_seg_size_fwd.unwrap_or(0) as f64,
+            self.active.mean(),
+            self.active.std(),
+            self.active.max,
+            self.active.min,
+            self.idle.mean(),
+            self.idle.std(),
+            self.idle.max,
+            self.idle.min,
+        ]
+    }
+
+    /// The 78-feature XGBoost vector: destination port followed by the LSTM vector.
+    pub fn xgb_features(&self) -> [f64; XGB_FEATURE_COUNT] {
+        let mut out = [0.0; XGB_FEATURE_COUNT];
+        out[0] = self.dst_port as f64;
+        out[1..].copy_from_slice(&self.lstm_features());
+        out
+    }
+}
+
+// ---------------------------------------------------------------------
+// FLOW TABLE
+// ---------------------------------------------------------------------
+pub struct FlowTable {
+    flows: HashMap<FlowKey, Flow>,
+    idle_timeout_us: i64,
+    active_timeout_us: i64,
+}
+
+impl FlowTable {
+    pub fn new() -> Self {
+        Self {
+            flows: HashMap::new(),
+            idle_timeout_us: IDLE_TIMEOUT_US,
+            active_timeout_us: ACTIVE_TIMEOUT_US,
+        }
+    }
+
+    fn is_expired(&self, flow: &Flow, now_us: i64) -> bool {
+        now_us - flow.last_seen_us > self.idle_timeout_us
+            || now_us - flow.first_seen_us > self.active_timeout_us
+    }
+
+    /// Meters one packet. Returns the previous flow on the same 5-tuple
+    /// if this packet arrived after it timed out.
+    pub fn push(&mut self, packet: &Packet) -> Option<Flow> {
+        let key = FlowKey::of(packet);
+
+        let expired = match self.flows.get(&key) {
+            Some(flow) if self.is_expired(flow, packet.timestamp_us) => self.flows.remove(&key),
+            _ => None,
+        };
+
+        self.flows
+            .entry(key)
+            .or_insert_with(|| Flow::new(packet))
+            .add(packet);
+
+        expired.map(|mut flow| {
+            flow.finish();
+            flow
+        })
+    }
+
+    /// Removes and returns every flow that is idle or active-timed-out at `now_us`.
+    pub fn expire(&mut self, now_us: i64) -> Vec<Flow> {
+        let keys: Vec<FlowKey> = self
+            .flows
+            .iter()
+            .filter(|(_, flow)| self.is_expired(flow, now_us))
+            .map(|(key, _)| *key)
+            .collect();
+
+        keys.into_iter()
+            .filter_map(|key| self.flows.remove(&key))
+            .map(|mut flow| {
+                flow.finish();
+                flow
+            })
+            .collect()
+    }
+
+    /// Removes and returns every remaining flow, e.g. at the end of a capture.
+    pub fn drain(&mut self) -> Vec<Flow> {
+        self.flows
+            .drain()
+            .map(|(_, mut flow)| {
+                flow.finish();
+                flow
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::packets::parse_packet;
+    use std::time::Duration;
+
+    impl FlowTable {
+        fn with_timeouts(idle: Duration, active: Duration) -> Self {
+            Self {
+                flows: HashMap::new(),
+                idle_timeout_us: idle.as_micros() as i64,
+                active_timeout_us: active.as_micros() as i64,
+            }
+        }
+
+        fn len(&self) -> usize {
+            self.flows.len()
+        }
+    }
+
+    impl Flow {
+        fn fwd_packets(&self) -> u64 {
+            self.fwd.lengths.count
+        }
+    }
+
+    // 10.0.0.2:51000 → 93.184.216.34:80 over Ethernet, 10 ms apart:
+    // SYN, SYN-ACK, ACK, PSH-ACK with 100 bytes, PSH-ACK back with 300
+    // bytes 20 ms later, FIN-ACK
+    const TCP_SESSION: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/tcp_session.pcap"
+    ));
+
+    /// Reads a little-endian classic pcap: the linktype and each frame
+    /// with its timestamp in microseconds.
+    fn read_pcap(data: &[u8]) -> (i32, Vec<(&[u8], i64)>) {
+        let u32_at = |at: usize| u32::from_le_bytes(data[at..at + 4].try_into().unwrap());
+        assert_eq!(u32_at(0), 0xa1b2c3d4);
+
+        let mut frames = Vec::new();
+        let mut at = 24;
+        while at < data.len() {
+            let ts = u32_at(at) as i64 * 1_000_000 + u32_at(at + 4) as i64;
+            let len = u32_at(at + 8) as usize;
+            frames.push((&data[at + 16..at + 16 + len], ts));
+            at += 16 + len;
+        }
+
+        (u32_at(20) as i32, frames)
+    }
+
+    fn meter(data: &[u8]) -> Vec<Flow> {
+        let (linktype, frames) = read_pcap(data);
+        let mut table = FlowTable::new();
+
+        let mut flows: Vec<Flow> = frames
+            .into_iter()
+            .filter_map(|(frame, ts)| table.push(&parse_packet(linktype, frame, ts)?))
+            .collect();
+        flows.extend(table.drain());
+        flows
+    }
+
+    fn feature(flow: &Flow, name: &str) -> f64 {
+        let index = LSTM_FEATURES.iter().position(|f| *f == name).unwrap();
+        flow.lstm_features()[index]
+    }
+
+    fn assert_features(flow: &Flow, expected: &[(&str, f64)]) {
+        for (name, value) in expected {
+            let actual = feature(flow, name);
+            assert!((actual - value).abs() < 1e-3, "{name}: {actual} != {value}");
+        }
+    }
+
+    #[test]
+    fn meters_one_bidirectional_flow_from_the_initiator() {
+        let flows = meter(TCP_SESSION);
+        assert_eq!(flows.len(), 1);
+
+        let flow = &flows[0];
+        assert_eq!(flow.src_ip.to_string(), "10.0.0.2");
+        assert_eq!(
+            (flow.src_port, flow.dst_port, flow.protocol),
+            (51000, 80, PROTO_TCP)
+        );
+        assert_eq!((flow.fwd_packets(), flow.bwd_packets()), (4, 2));
+        assert_eq!((flow.fwd_bytes(), flow.bwd_bytes()), (100, 300));
+
+        let flags = flow.fwd_flags().unwrap();
+        assert_eq!((flags.syn, flags.ack, flags.rst, flags.fin), (1, 3, 0, 1));
+    }
+
+    #[test]
+    fn lstm_vector_matches_cicflowmeter() {
+        let flow = &meter(TCP_SESSION)[0];
+
+        assert_features(
+            flow,
+            &[
+                ("Flow Duration", 60_000.0),
+                ("Total Fwd Packets", 4.0),
+                ("Total Backward Packets", 2.0),
+                ("Total Length of Fwd Packets", 100.0),
+                ("Total Length of Bwd Packets", 300.0),
+                ("Fwd Packet Length Max", 100.0),
+                ("Fwd Packet Length Min", 0.0),
+                ("Fwd Packet Length Mean", 25.0),
+                ("Fwd Packet Length Std", 50.0),
+                ("Bwd Packet Length Mean", 150.0),
+                ("Bwd Packet Length Std", 212.132),
+                ("Flow Bytes/s", 6666.667),
+                ("Flow Packets/s", 100.0),
+                ("Flow IAT Mean", 12_000.0),
+                ("Flow IAT Std", 4472.136),
+                ("Flow IAT Max", 20_000.0),
+                ("Flow IAT Min", 10_000.0),
+                ("Fwd IAT Total", 60_000.0),
+                ("Fwd IAT Mean", 20_000.0),
+                ("Fwd IAT Std", 10_000.0),
+                ("Bwd IAT Total", 40_000.0),
+                ("Bwd IAT Std", 0.0),
+                ("Fwd PSH Flags", 1.0),
+                ("Bwd PSH Flags", 1.0),
+                ("Fwd Header Length", 80.0),
+                ("Bwd Header Length", 40.0),
+                ("Fwd Packets/s", 66.667),
+                ("Packet Length Mean", 66.667),
+                ("FIN Flag Count", 1.0),
+                ("SYN Flag Count", 2.0),
+                ("PSH Flag Count", 2.0),
+                ("ACK Flag Count", 5.0),
+                ("Down/Up Ratio", 0.5),
+                ("Subflow Fwd Packets", 4.0),
+                ("Subflow Bwd Bytes", 300.0),
+                ("Init_Win_bytes_forward", 64240.0),
+                ("Init_Win_bytes_backward", 65535.0),
+                ("act_data_pkt_fwd", 1.0),
+                ("min_seg_size_forward", 20.0),
+                ("Active Mean", 60_000.0),
+                ("Idle Mean", 0.0),
+            ],
+        );
+    }
+
+    #[test]
+    fn xgb_vector_is_destination_port_then_lstm_vector() {
+        let flow = &meter(TCP_SESSION)[0];
+        let xgb = flow.xgb_features();
+
+        assert_eq!(xgb.len(), XGB_FEATURE_COUNT);
+        assert_eq!(xgb[0], 80.0);
+        assert_eq!(xgb[1..], flow.lstm_features());
+    }
+
+    #[test]
+    fn idle_timeout_splits_a_reused_tuple() {
+        let (linktype, frames) = read_pcap(TCP_SESSION);
+        let mut table =
+            FlowTable::with_timeouts(Duration::from_millis(15), Duration::from_secs(60));
+
+        let expired: Vec<Flow> = frames
+            .into_iter()
+            .filter_map(|(frame, ts)| table.push(&parse_packet(linktype, frame, ts)?))
+            .collect();
+
+        // Only the 20 ms gap before the server's data exceeds the timeout
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].fwd_packets() + expired[0].bwd_packets(), 4);
+        assert_eq!(table.len(), 1);
+    }
+}
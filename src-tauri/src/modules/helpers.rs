@@ -1,6 +1,56 @@
 use crate::modules::structures::NetworkInfo;
 use std::cmp::Ordering;
 
+/// Case-insensitive shell-style match: `*` is any run, `?` any one character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Last `*` seen and the text position it is currently matched up to
+    let mut star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((sp, st)) => {
+                    p = sp + 1;
+                    t = st + 1;
+                    star = Some((sp, st + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Surrounding whitespace is ignored.
+pub fn from_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim();
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
 pub fn rigid_sorter(mut interfaces: Vec<NetworkInfo>) -> Vec<NetworkInfo> {
     interfaces.sort_by(
         |a, b| match (a.anomaly.is_anomalous, b.anomaly.is_anomalous) {
@@ -16,16 +66,3 @@ pub fn rigid_sorter(mut interfaces: Vec<NetworkInfo>) -> Vec<NetworkInfo> {
 
     interfaces
 }
-
-pub fn mask_ip(ip: &str) -> String {
-    if ip.starts_with("192.") || ip.starts_with("10.") || ip.starts_with("172.") {
-        ip.to_string()
-    } else {
-        let parts: Vec<&str> = ip.split('.').collect();
-        if parts.len() == 4 {
-            format!("{}.{}.x.x", parts[0], parts[1])
-        } else {
-            ip.to_string()
-        }
-    }
-}
\ No newline at end of file
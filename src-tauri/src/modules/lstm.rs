@@ -0,0 +1,240 @@
+use crate::modules::flows::{LSTM_FEATURES, LSTM_FEATURE_COUNT};
+use crate::modules::scaler::StandardScaler;
+use crate::AnomalyDetection;
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tract_onnx::prelude::*;
+
+type Plan = TypedRunnableModel<TypedModel>;
+
+/// Outcome of one reconstruction pass. `error` is the mean squared
+/// reconstruction error of the scaled feature vector.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LstmVerdict {
+    pub error: f64,
+    pub threshold: f64,
+    pub is_anomaly: bool,
+}
+
+impl LstmVerdict {
+    // Same labels the Python worker reported for the autoencoder
+    pub fn label(&self) -> &'static str {
+        if self.is_anomaly {
+            "unknown"
+        } else {
+            "benign"
+        }
+    }
+}
+
+impl From<&LstmVerdict> for AnomalyDetection {
+    fn from(verdict: &LstmVerdict) -> Self {
+        Self {
+            is_anomalous: verdict.is_anomaly,
+            anomaly_type: Some(verdict.label().to_uppercase()),
+        }
+    }
+}
+
+pub struct LstmModel {
+    plan: Plan,
+    scaler: Option<StandardScaler>,
+    threshold: f64,
+}
+
+impl LstmModel {
+    /// Loads `lstm_autoencoder.onnx`, `lstm_threshold.npy` and, when present,
+    /// `lstm_scaler.json` from the `models/LSTM` directory.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let onnx = dir.join("lstm_autoencoder.onnx");
+
+        let plan = tract_onnx::onnx()
+            .model_for_path(&onnx)
+            // The export declares symbolic batch/seq dims; pin both ends to one flow
+            .and_then(|m| m.with_input_fact(0, f32::fact([1, 1, LSTM_FEATURE_COUNT]).into()))
+            .and_then(|m| m.with_output_fact(0, f32::fact([1, 1, LSTM_FEATURE_COUNT]).into()))
+            .and_then(|m| m.into_optimized())
+            .and_then(|m| m.into_runnable())
+            .map_err(|e| format!("Failed to load {}: {e}", onnx.display()))?;
+
+        let threshold = read_npy_scalar(&dir.join("lstm_threshold.npy"))?;
+
+        let scaler_path = dir.join("lstm_scaler.json");
+        let scaler = if scaler_path.exists() {
+            let scaler = StandardScaler::load(&scaler_path)?;
+            scaler.expect_features(&LSTM_FEATURES)?;
+            Some(scaler)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            plan,
+            scaler,
+            threshold,
+        })
+    }
+
+    #[cfg(test)]
+    fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    /// `threshold` replaces the shipped one when set.
+    pub fn score(
+        &self,
+        features: &[f64; LSTM_FEATURE_COUNT],
+        threshold: Option<f64>,
+    ) -> Result<LstmVerdict, String> {
+        let scaled = match &self.scaler {
+            Some(scaler) => scaler.transform(features),
+            None => features.to_vec(),
+        };
+
+        let input: Vec<f32> = scaled.iter().map(|v| *v as f32).collect();
+        let tensor = Tensor::from_shape(&[1, 1, LSTM_FEATURE_COUNT], &input)
+            .map_err(|e| e.to_string())?;
+
+        let outputs = self
+            .plan
+            .run(tvec!(tensor.into()))
+            .map_err(|e| format!("LSTM inference failed: {e}"))?;
+
+        let reconstructed = outputs[0]
+            .as_slice::<f32>()
+            .map_err(|e| format!("Unexpected LSTM output: {e}"))?;
+
+        if reconstructed.len() != input.len() {
+            return Err(format!(
+                "LSTM output has {} values, expected {}",
+                reconstructed.len(),
+                input.len()
+            ));
+        }
+
+        let error = input
+            .iter()
+            .zip(reconstructed)
+            .map(|(a, b)| ((a - b) as f64).powi(2))
+            .sum::<f64>()
+            / input.len() as f64;
+
+        let threshold = threshold.unwrap_or(self.threshold);
+        Ok(LstmVerdict {
+            error,
+            threshold,
+            is_anomaly: error > threshold,
+        })
+    }
+}
+
+// ---- Minimal .npy reader: a single little-endian float scalar ----
+fn read_npy_scalar(path: &Path) -> Result<f64, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    parse_npy_scalar(&bytes)
+        .ok_or_else(|| format!("{} is not a scalar float .npy file", path.display()))
+}
+
+fn parse_npy_scalar(bytes: &[u8]) -> Option<f64> {
+    if bytes.get(..6)? != b"\x93NUMPY" {
+        return None;
+    }
+
+    // v1 uses a u16 header length, v2+ a u32
+    let (header_start, header_len) = match *bytes.get(6)? {
+        1 => (10, u16::from_le_bytes(bytes.get(8..10)?.try_into().ok()?) as usize),
+        _ => (12, u32::from_le_bytes(bytes.get(8..12)?.try_into().ok()?) as usize),
+    };
+
+    let header = String::from_utf8_lossy(bytes.get(header_start..header_start + header_len)?);
+    let data = &bytes[header_start + header_len..];
+
+    if header.contains("'<f8'") {
+        Some(f64::from_le_bytes(data.get(..8)?.try_into().ok()?))
+    } else if header.contains("'<f4'") {
+        Some(f32::from_le_bytes(data.get(..4)?.try_into().ok()?) as f64)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn bundled() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("models").join("LSTM")
+    }
+
+    // A scalar .npy file as numpy writes it, header padded with spaces
+    fn npy(version: u8, descr: &str, data: &[u8]) -> Vec<u8> {
+        let mut header = format!("{{'descr': '{descr}', 'fortran_order': False, 'shape': (), }}");
+        let prefix = if version == 1 { 10 } else { 12 };
+        while (prefix + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+
+        let mut out = b"\x93NUMPY".to_vec();
+        out.extend([version, 0]);
+        match version {
+            1 => out.extend((header.len() as u16).to_le_bytes()),
+            _ => out.extend((header.len() as u32).to_le_bytes()),
+        }
+        out.extend(header.as_bytes());
+        out.extend(data);
+        out
+    }
+
+    #[test]
+    fn bundled_model_loads_and_scores() {
+        let model = LstmModel::load(&bundled()).unwrap();
+        assert!(model.threshold().is_finite() && model.threshold() > 0.0);
+
+        let verdict = model.score(&[0.0; LSTM_FEATURE_COUNT], None).unwrap();
+        assert!(verdict.error.is_finite() && verdict.error >= 0.0);
+        assert_eq!(verdict.threshold, model.threshold());
+
+        // The override replaces the shipped threshold
+        let verdict = model.score(&[0.0; LSTM_FEATURE_COUNT], Some(f64::MAX)).unwrap();
+        assert!(!verdict.is_anomaly);
+    }
+
+    #[test]
+    fn bundled_threshold_is_a_v1_f8_scalar() {
+        let bytes = fs::read(bundled().join("lstm_threshold.npy")).unwrap();
+        assert_eq!(bytes[6], 1);
+        assert!(parse_npy_scalar(&bytes).is_some());
+    }
+
+    #[test]
+    fn npy_v1_and_v2_headers_are_read() {
+        let value = 0.125f64;
+        assert_eq!(parse_npy_scalar(&npy(1, "<f8", &value.to_le_bytes())), Some(value));
+        assert_eq!(parse_npy_scalar(&npy(2, "<f8", &value.to_le_bytes())), Some(value));
+        assert_eq!(
+            parse_npy_scalar(&npy(1, "<f4", &(value as f32).to_le_bytes())),
+            Some(value)
+        );
+        assert_eq!(parse_npy_scalar(&npy(1, "<i8", &1i64.to_le_bytes())), None);
+    }
+
+    #[test]
+    fn truncated_npy_is_an_error() {
+        let full = npy(2, "<f8", &1.0f64.to_le_bytes());
+
+        // Every prefix, from the magic string to the last data byte
+        for len in 0..full.len() {
+            assert_eq!(parse_npy_scalar(&full[..len]), None, "{len} bytes");
+        }
+
+        // A header length past the end of the file
+        let mut lying = full.clone();
+        lying[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(parse_npy_scalar(&lying), None);
+    }
+}
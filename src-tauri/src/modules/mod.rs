@@ -2,4 +2,29 @@ pub mod python_runner;
 pub mod macros;
 pub mod structures;
 pub mod helpers;
-pub mod networks;
\ No newline at end of file
+pub mod networks;
+pub mod packets;
+pub mod flows;
+pub mod capture;
+pub mod scaler;
+pub mod lstm;
+pub mod xgboost;
+pub mod detector;
+pub mod offline;
+pub mod notifier;
+pub mod daemon;
+pub mod supervisor;
+pub mod protocol;
+pub mod alerts;
+pub mod storage;
+pub mod bandwidth;
+pub mod privacy;
+pub mod config;
+pub mod monitoring;
+pub mod deltas;
+pub mod pipeline;
+pub mod scan;
+pub mod beacon;
+pub mod dns;
+pub mod tls;
+pub mod arp;
\ No newline at end of file
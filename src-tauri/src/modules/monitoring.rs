@@ -0,0 +1,141 @@
+use crate::modules::config::{apply_config, MonitorConfig};
+use crate::AppState;
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+/// A per-interface choice saved in `config.toml` under `[monitor.interfaces]`.
+/// Interfaces without one follow the include/exclude rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterfaceControl {
+    Started,
+    Paused,
+    Stopped,
+}
+
+/// What the backend is doing with one interface, as reported on `NetworkInfo`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitoringState {
+    Monitoring,
+    // Capture kept running, verdicts dropped
+    Paused,
+    // Turned off by the user
+    Stopped,
+    // Turned off by an exclude rule
+    Excluded,
+    // No capture device matched the interface
+    #[default]
+    Unavailable,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MonitorAction {
+    Start,
+    Stop,
+    Pause,
+    Resume,
+}
+
+/// `device` is the capture device, `None` when the interface can't be captured on.
+pub fn resolve_state(monitor: &MonitorConfig, name: &str, device: Option<&str>) -> MonitoringState {
+    let Some(device) = device else {
+        return MonitoringState::Unavailable;
+    };
+
+    match monitor.interfaces.get(name) {
+        Some(InterfaceControl::Started) => MonitoringState::Monitoring,
+        Some(InterfaceControl::Paused) => MonitoringState::Paused,
+        Some(InterfaceControl::Stopped) => MonitoringState::Stopped,
+        None if monitor.monitors(name, device) => MonitoringState::Monitoring,
+        None => MonitoringState::Excluded,
+    }
+}
+
+/// Applies a user action to the interface `id` and saves it to the config.
+/// Only deviations from the include/exclude rules are stored.
+pub fn control_interface(
+    state: &AppState,
+    id: &str,
+    action: MonitorAction,
+) -> Result<MonitoringState, String> {
+    let name = state
+        .networks
+        .read()
+        .unwrap()
+        .iter()
+        .find(|net| net.id == id)
+        .map(|net| net.name.clone())
+        .ok_or_else(|| format!("Unknown interface '{id}'"))?;
+    let device = state
+        .iface_map
+        .lock()
+        .unwrap()
+        .get(id)
+        .cloned()
+        .ok_or_else(|| format!("{name} has no capture device"))?;
+    let path = state
+        .config_path
+        .read()
+        .unwrap()
+        .clone()
+        .ok_or("Configuration file is unavailable")?;
+
+    let mut config = state.config.read().unwrap().clone();
+    let current = resolve_state(&config.monitor, &name, Some(&device));
+
+    match action {
+        MonitorAction::Pause if current != MonitoringState::Monitoring => {
+            return Err(format!("{name} is not being monitored"));
+        }
+        MonitorAction::Resume if current != MonitoringState::Paused => {
+            return Err(format!("{name} is not paused"));
+        }
+        _ => {}
+    }
+
+    let by_rules = config.monitor.monitors(&name, &device);
+    let control = match action {
+        MonitorAction::Start | MonitorAction::Resume => {
+            (!by_rules).then_some(InterfaceControl::Started)
+        }
+        MonitorAction::Stop => by_rules.then_some(InterfaceControl::Stopped),
+        MonitorAction::Pause => Some(InterfaceControl::Paused),
+    };
+    match control {
+        Some(control) => config.monitor.interfaces.insert(name.clone(), control),
+        None => config.monitor.interfaces.remove(&name),
+    };
+
+    let next = resolve_state(&config.monitor, &name, Some(&device));
+    config.save(&path).map_err(|e| e.to_string())?;
+    apply_config(state, config);
+
+    Ok(next)
+}
+
+/// Lets commands cut the monitor loop's sleep short so changes apply at once.
+#[derive(Debug, Default)]
+pub struct Wakeup {
+    pending: Mutex<bool>,
+    cond: Condvar,
+}
+
+impl Wakeup {
+    pub fn notify(&self) {
+        *self.pending.lock().unwrap() = true;
+        self.cond.notify_all();
+    }
+
+    /// Sleeps for `timeout`, or until `notify` is called.
+    pub fn wait(&self, timeout: Duration) {
+        let pending = self.pending.lock().unwrap();
+        let (mut pending, _) = self
+            .cond
+            .wait_timeout_while(pending, timeout, |pending| !*pending)
+            .unwrap();
+        *pending = false;
+    }
+}
@@ -1,19 +1,78 @@
-use crate::{AnomalyDetection, Bandwidth, IPInfo};
-use crate::{mask_ip, rigid_sorter};
+use crate::modules::bandwidth::RateTracker;
+use crate::modules::monitoring::MonitoringState;
+use crate::modules::protocol::FlowTuple;
+use crate::modules::packets::{PROTO_TCP, PROTO_UDP};
+use crate::{AnomalyDetection, Connection, IPInfo, ProcessInfo};
+use crate::rigid_sorter;
 
 use netstat2::{
     get_sockets_info, AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, SocketInfo,
 };
 use network_interface::{NetworkInterface, NetworkInterfaceConfig};
-use std::collections::HashMap;
-use std::net::IpAddr;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use chrono::Utc;
-use sysinfo::Pid;
+use sysinfo::{Pid, Users};
 use crate::ProcessesToUpdate;
 use crate::NetworkInfo;
 use crate::Networks;
 use crate::System;
 
+/// One capture device as listed by `helper.py`, or by libpcap without it.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CaptureDevice {
+    pub name: String,
+    #[serde(default)]
+    pub ipv4: Option<String>,
+    #[serde(default)]
+    pub ipv6: Vec<String>,
+    #[serde(default)]
+    pub mac: Option<String>,
+}
+
+impl CaptureDevice {
+    fn addrs(&self) -> impl Iterator<Item = IpAddr> + '_ {
+        self.ipv4
+            .iter()
+            .chain(self.ipv6.iter())
+            .filter_map(|ip| ip.split('%').next()?.parse().ok())
+    }
+}
+
+fn normalize_mac(mac: &str) -> Option<String> {
+    let mac = mac.trim().to_lowercase().replace('-', ":");
+
+    if mac.is_empty() || mac == "00:00:00:00:00:00" {
+        None
+    } else {
+        Some(mac)
+    }
+}
+
+/// Stable identity of an OS interface: index, name and MAC.
+/// Used as the key for `AppState.networks`, `workers` and `iface_map`.
+pub fn interface_key(index: u32, name: &str, mac: Option<&str>) -> String {
+    format!("{index}/{name}/{}", mac.unwrap_or("-"))
+}
+
+/// Picks the capture device for an OS interface: same MAC first, then
+/// same name (Linux, macOS), then any shared address.
+fn match_capture_device<'a>(
+    devices: &'a [CaptureDevice],
+    taken: &HashSet<&str>,
+    name: &str,
+    mac: Option<&str>,
+    addrs: &[IpAddr],
+) -> Option<&'a CaptureDevice> {
+    let free = || devices.iter().filter(|d| !taken.contains(d.name.as_str()));
+
+    free()
+        .find(|d| mac.is_some() && d.mac.as_deref().and_then(normalize_mac).as_deref() == mac)
+        .or_else(|| free().find(|d| d.name == name))
+        .or_else(|| free().find(|d| d.addrs().any(|ip| addrs.contains(&ip))))
+}
+
 fn load_interfaces() -> (Vec<NetworkInterface>, HashMap<IpAddr, String>) {
     let interfaces = NetworkInterface::show().unwrap_or_default();
 
@@ -25,6 +84,28 @@ fn load_interfaces() -> (Vec<NetworkInterface>, HashMap<IpAddr, String>) {
     (interfaces, iface_ips)
 }
 
+/// The IPv4 default gateway routed through `iface`, from the kernel route
+/// table. Other platforms don't expose one without shelling out.
+#[cfg(target_os = "linux")]
+pub fn default_gateway(iface: &str) -> Option<Ipv4Addr> {
+    let routes = std::fs::read_to_string("/proc/net/route").ok()?;
+
+    // Iface, Destination, Gateway, ...; addresses in hex, as stored in memory
+    routes.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 3 || fields[0] != iface || fields[1] != "00000000" {
+            return None;
+        }
+        let gateway = u32::from_str_radix(fields[2], 16).ok()?;
+        Some(Ipv4Addr::from(gateway.to_ne_bytes())).filter(|ip| !ip.is_unspecified())
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn default_gateway(_iface: &str) -> Option<Ipv4Addr> {
+    None
+}
+
 fn load_sockets() -> Vec<SocketInfo> {
     get_sockets_info(
         AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6,
@@ -33,14 +114,202 @@ fn load_sockets() -> Vec<SocketInfo> {
     .unwrap_or_default()
 }
 
-fn extract_ports(sockets: &[SocketInfo]) -> Vec<u16> {
-    sockets
+fn to_connection(socket: &SocketInfo, sys: &System) -> (IpAddr, Connection) {
+    let pid = socket.associated_pids.first().copied();
+    let process = pid
+        .and_then(|pid| sys.process(Pid::from(pid as usize)))
+        .map(|p| p.name().to_string_lossy().to_string());
+
+    match &socket.protocol_socket_info {
+        ProtocolSocketInfo::Tcp(tcp) => {
+            // Listening sockets report 0.0.0.0:0 / [::]:0 as their peer
+            let connected = !tcp.remote_addr.is_unspecified() || tcp.remote_port != 0;
+
+            (
+                tcp.local_addr,
+                Connection {
+                    protocol: "tcp".into(),
+                    local_addr: tcp.local_addr.to_string(),
+                    local_port: tcp.local_port,
+                    remote_addr: connected.then(|| tcp.remote_addr.to_string()),
+                    remote_port: connected.then_some(tcp.remote_port),
+                    state: Some(format!("{:?}", tcp.state)),
+                    pid,
+                    process,
+                },
+            )
+        }
+        ProtocolSocketInfo::Udp(udp) => (
+            udp.local_addr,
+            Connection {
+                protocol: "udp".into(),
+                local_addr: udp.local_addr.to_string(),
+                local_port: udp.local_port,
+                remote_addr: None,
+                remote_port: None,
+                state: None,
+                pid,
+                process,
+            },
+        ),
+    }
+}
+
+/// Every process holding at least one socket, busiest first.
+fn build_processes(sockets: &[SocketInfo], sys: &System) -> Vec<ProcessInfo> {
+    let users = Users::new_with_refreshed_list();
+    let mut by_pid: HashMap<u32, ProcessInfo> = HashMap::new();
+
+    for socket in sockets {
+        let Some(&pid) = socket.associated_pids.first() else {
+            continue;
+        };
+        let (_, conn) = to_connection(socket, sys);
+
+        let entry = by_pid.entry(pid).or_insert_with(|| {
+            let process = sys.process(Pid::from(pid as usize));
+
+            ProcessInfo {
+                pid,
+                name: process
+                    .map(|p| p.name().to_string_lossy().to_string())
+                    .unwrap_or_else(|| format!("pid {pid}")),
+                exe: process
+                    .and_then(|p| p.exe())
+                    .map(|path| path.display().to_string()),
+                user: process
+                    .and_then(|p| p.user_id())
+                    .and_then(|uid| users.get_user_by_id(uid))
+                    .map(|user| user.name().to_string()),
+                cmd: process
+                    .map(|p| p.cmd().iter().map(|a| a.to_string_lossy().to_string()).collect())
+                    .unwrap_or_default(),
+                cpu_usage: process.map_or(0.0, |p| p.cpu_usage()),
+                connections: Vec::new(),
+            }
+        });
+        entry.connections.push(conn);
+    }
+
+    let mut processes: Vec<ProcessInfo> = by_pid.into_values().collect();
+    processes.sort_by(|a, b| {
+        b.connections
+            .len()
+            .cmp(&a.connections.len())
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    processes
+}
+
+fn addr_matches(bound: &str, ip: &str) -> bool {
+    match (bound.parse::<IpAddr>(), ip.parse::<IpAddr>()) {
+        (Ok(bound), Ok(ip)) => {
+            let bound = bound.to_canonical();
+            bound.is_unspecified() || bound == ip.to_canonical()
+        }
+        _ => false,
+    }
+}
+
+fn is_local(ip: &str, local_addrs: &HashSet<IpAddr>) -> bool {
+    ip.parse::<IpAddr>()
+        .is_ok_and(|ip| local_addrs.contains(&ip.to_canonical()))
+}
+
+/// Whether `conn` is the local end of `flow`, in either direction.
+/// `local_addrs` are this host's interface addresses: a listener only owns
+/// flows whose end on its port is one of them, not traffic merely seen on it.
+fn owns_flow(conn: &Connection, flow: &FlowTuple, local_addrs: &HashSet<IpAddr>) -> bool {
+    let protocol = match flow.protocol {
+        PROTO_TCP => "tcp",
+        PROTO_UDP => "udp",
+        _ => return false,
+    };
+    if conn.protocol != protocol {
+        return false;
+    }
+
+    let ends = [
+        (&flow.src_ip, flow.src_port, &flow.dst_ip, flow.dst_port),
+        (&flow.dst_ip, flow.dst_port, &flow.src_ip, flow.src_port),
+    ];
+
+    ends.iter().any(|(local_ip, local_port, remote_ip, remote_port)| {
+        conn.local_port == *local_port
+            && addr_matches(&conn.local_addr, local_ip)
+            && match (&conn.remote_addr, conn.remote_port) {
+                (Some(addr), Some(port)) => port == *remote_port && addr_matches(addr, remote_ip),
+                // Listening or unconnected UDP socket
+                _ => is_local(local_ip, local_addrs),
+            }
+    })
+}
+
+/// The process behind a flow among `processes`. Connected sockets win over
+/// listeners.
+pub fn flow_owner(
+    flow: &FlowTuple,
+    processes: &[ProcessInfo],
+    local_addrs: &HashSet<IpAddr>,
+) -> Option<(u32, String)> {
+    processes
         .iter()
-        .map(|s| match &s.protocol_socket_info {
-            ProtocolSocketInfo::Tcp(tcp) => tcp.local_port,
-            ProtocolSocketInfo::Udp(udp) => udp.local_port,
+        .flat_map(|p| {
+            p.connections
+                .iter()
+                .filter(|c| owns_flow(c, flow, local_addrs))
+                .map(move |c| (c.remote_addr.is_some(), p))
         })
-        .collect()
+        .max_by_key(|(connected, _)| *connected)
+        .map(|(_, p)| (p.pid, p.name.clone()))
+}
+
+/// A fresh socket table, for flows that started since the last monitor cycle.
+/// Only the processes holding sockets are refreshed.
+pub fn scan_processes() -> Vec<ProcessInfo> {
+    let sockets = load_sockets();
+    let mut sys = System::new();
+    let pids: Vec<Pid> = sockets
+        .iter()
+        .filter_map(|s| s.associated_pids.first())
+        .map(|pid| Pid::from(*pid as usize))
+        .collect();
+    sys.refresh_processes(ProcessesToUpdate::Some(&pids), true);
+
+    build_processes(&sockets, &sys)
+}
+
+/// Groups sockets by the interface owning their local address.
+/// Wildcard binds are returned separately.
+fn group_connections(
+    sockets: &[SocketInfo],
+    iface_ips: &HashMap<IpAddr, String>,
+    sys: &System,
+) -> (HashMap<String, Vec<Connection>>, Vec<Connection>) {
+    let mut by_iface: HashMap<String, Vec<Connection>> = HashMap::new();
+    let mut wildcard = Vec::new();
+
+    for socket in sockets {
+        let (local, conn) = to_connection(socket, sys);
+        let local = local.to_canonical();
+
+        if local.is_unspecified() {
+            wildcard.push(conn);
+        } else if let Some(iface_name) = iface_ips.get(&local) {
+            by_iface.entry(iface_name.clone()).or_default().push(conn);
+        }
+    }
+
+    let order = |a: &Connection, b: &Connection| {
+        (&a.protocol, a.local_port, &a.remote_addr).cmp(&(&b.protocol, b.local_port, &b.remote_addr))
+    };
+    for conns in by_iface.values_mut() {
+        conns.sort_by(order);
+    }
+    wildcard.sort_by(order);
+
+    (by_iface, wildcard)
 }
 
 fn map_pids_to_interfaces(
@@ -86,71 +355,124 @@ fn compute_interface_cpu(
     map
 }
 
-fn build_ip_info(interfaces: &[NetworkInterface], name: &str) -> IPInfo {
-    if let Some(iface) = interfaces.iter().find(|i| i.name == name) {
-        let ipv4 = iface
-            .addr
-            .iter()
-            .find(|a| matches!(a.ip(), IpAddr::V4(_)))
-            .map(|a| a.ip().to_string());
+fn is_ipv6_link_local(ip: &Ipv6Addr) -> bool {
+    (ip.segments()[0] & 0xffc0) == 0xfe80
+}
 
-        let ipv6 = iface
-            .addr
-            .iter()
-            .find(|a| matches!(a.ip(), IpAddr::V6(_)))
-            .map(|a| a.ip().to_string());
+fn build_ip_info(interfaces: &[NetworkInterface], name: &str) -> IPInfo {
+    let mut v4: Vec<Ipv4Addr> = Vec::new();
+    let mut v6: Vec<Ipv6Addr> = Vec::new();
 
-        return IPInfo {
-            ipv4: ipv4.map(|ip| mask_ip(&ip)),
-            ipv6,
-        };
+    // Some platforms list an interface once per address
+    for iface in interfaces.iter().filter(|i| i.name == name) {
+        for addr in &iface.addr {
+            match addr.ip() {
+                IpAddr::V4(ip) if !v4.contains(&ip) => v4.push(ip),
+                IpAddr::V6(ip) if !v6.contains(&ip) => v6.push(ip),
+                _ => {}
+            }
+        }
     }
 
+    // Global addresses first so the headline IPv6 is not a link-local one
+    v6.sort_by_key(is_ipv6_link_local);
+
+    // Unmasked here, the privacy policy is applied wherever networks leave the backend
+    let ipv4_addrs: Vec<String> = v4.iter().map(|ip| ip.to_string()).collect();
+    let ipv6_addrs: Vec<String> = v6.iter().map(|ip| ip.to_string()).collect();
+
     IPInfo {
-        ipv4: None,
-        ipv6: None,
+        ipv4: ipv4_addrs.first().cloned(),
+        ipv6: ipv6_addrs.first().cloned(),
+        ipv4_addrs,
+        ipv6_addrs,
     }
 }
 
 fn build_network_info(
-    interfaces: &Vec<NetworkInterface>,
-    sockets: &Vec<SocketInfo>,
+    interfaces: &[NetworkInterface],
+    mut connections: HashMap<String, Vec<Connection>>,
     iface_cpu: &HashMap<String, f32>,
-) -> Vec<NetworkInfo> {
+    rates: &RateTracker,
+    devices: &[CaptureDevice],
+) -> (Vec<NetworkInfo>, HashMap<String, String>) {
     let networks = Networks::new_with_refreshed_list();
     let mut results = Vec::new();
+    let mut capture_map = HashMap::new();
+    let mut taken = HashSet::new();
 
-    for (index, (name, data)) in networks.iter().enumerate() {
+    for (name, data) in networks.iter() {
         let rx_bytes = data.total_received();
         let tx_bytes = data.total_transmitted();
 
-        let ip_info = build_ip_info(&interfaces, name);
-        let ports = extract_ports(&sockets);
+        // Some platforms list an interface once per address
+        let entries: Vec<&NetworkInterface> =
+            interfaces.iter().filter(|i| &i.name == name).collect();
+        let index = entries.first().map_or(0, |i| i.index);
+        let mac = normalize_mac(&data.mac_address().to_string()).or_else(|| {
+            entries
+                .iter()
+                .find_map(|i| i.mac_addr.as_deref().and_then(normalize_mac))
+        });
+        let addrs: Vec<IpAddr> = entries
+            .iter()
+            .flat_map(|i| i.addr.iter().map(|a| a.ip()))
+            .collect();
+
+        let id = interface_key(index, name, mac.as_deref());
+        if let Some(device) = match_capture_device(devices, &taken, name, mac.as_deref(), &addrs) {
+            taken.insert(device.name.as_str());
+            capture_map.insert(id.clone(), device.name.clone());
+        }
+
+        let ip_info = build_ip_info(interfaces, name);
+        let connections = connections.remove(name.as_str()).unwrap_or_default();
+        let mut ports: Vec<u16> = connections.iter().map(|c| c.local_port).collect();
+        ports.sort_unstable();
+        ports.dedup();
         let anomaly = AnomalyDetection::new();
+        let (bandwidth, bandwidth_history) = rates.sample(name, rx_bytes, tx_bytes);
 
         results.push(NetworkInfo {
-            id: format!("iface_{index}"),
+            id,
             name: name.to_string(),
+            index,
+            mac,
             status: if rx_bytes + tx_bytes > 0 {
                 "active".into()
             } else {
                 "idle".into()
             },
+            // Decided by the monitor loop
+            monitoring_state: MonitoringState::default(),
             anomaly,
             ip_info,
-            bandwidth: Bandwidth{download : (rx_bytes as f64 * 8.0) / 1_000_000.0, upload : (tx_bytes as f64 * 8.0) / 1_000_000.0},
+            bandwidth,
+            bandwidth_history,
             received_bytes: rx_bytes,
             transmitted_bytes: tx_bytes,
             active_ports: ports,
+            connections,
             last_seen: Utc::now(),
             cpu_usage: *iface_cpu.get(name).unwrap_or(&0.0),
         });
     }
 
-    results
+    (results, capture_map)
+}
+
+/// One monitor cycle's view of the machine.
+pub struct NetworkScan {
+    pub networks: Vec<NetworkInfo>,
+    // Interface key → capture device name, for interfaces that can be captured on
+    pub iface_map: HashMap<String, String>,
+    pub wildcard_sockets: Vec<Connection>,
+    pub processes: Vec<ProcessInfo>,
+    // Every interface address, canonical, for `flow_owner`
+    pub local_addrs: HashSet<IpAddr>,
 }
 
-pub fn collect_networks() -> Vec<NetworkInfo> {
+pub fn collect_networks(rates: &RateTracker, devices: &[CaptureDevice]) -> NetworkScan {
     let mut sys = System::new_all();
     sys.refresh_all();
 
@@ -159,7 +481,19 @@ pub fn collect_networks() -> Vec<NetworkInfo> {
     let iface_pids = map_pids_to_interfaces(&sockets, &iface_ips);
     sys.refresh_processes(ProcessesToUpdate::All, true);
     let iface_cpu = compute_interface_cpu(&sys, iface_pids);
-    let results = build_network_info(&interfaces, &sockets, &iface_cpu);
+    let (connections, wildcard_sockets) = group_connections(&sockets, &iface_ips, &sys);
+    let processes = build_processes(&sockets, &sys);
+    let (results, iface_map) =
+        build_network_info(&interfaces, connections, &iface_cpu, rates, devices);
 
-    rigid_sorter(results)
+    let names: Vec<String> = results.iter().map(|n| n.name.clone()).collect();
+    rates.retain(&names);
+
+    NetworkScan {
+        networks: rigid_sorter(results),
+        iface_map,
+        wildcard_sockets,
+        processes,
+        local_addrs: iface_ips.keys().map(IpAddr::to_canonical).collect(),
+    }
 }
\ No newline at end of file
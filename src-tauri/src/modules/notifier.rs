@@ -0,0 +1,29 @@
+use crate::modules::deltas::NetworkDelta;
+use crate::modules::privacy::Redact;
+use crate::modules::protocol::{Envelope, WorkerMessage};
+use crate::{current_privacy, AppState};
+
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Where monitoring output goes: the Tauri webview, or stdout in the daemon.
+pub trait Notifier: Send + Sync + 'static {
+    /// Interfaces added, updated or removed since the previous delta.
+    fn network_delta(&self, delta: &NetworkDelta);
+
+    /// One worker message, after the pipeline has applied it to the state.
+    fn worker_message(&self, message: WorkerMessage);
+}
+
+impl Notifier for AppHandle {
+    fn network_delta(&self, delta: &NetworkDelta) {
+        let state = self.state::<Arc<AppState>>();
+        let _ = self.emit("network_delta", delta.clone().redact(&current_privacy(&state)));
+    }
+
+    fn worker_message(&self, message: WorkerMessage) {
+        let state = self.state::<Arc<AppState>>();
+        let message = message.redact(&current_privacy(&state));
+        let _ = self.emit("get_anomaly", Envelope::new(message));
+    }
+}
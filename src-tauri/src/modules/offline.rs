@@ -0,0 +1,172 @@
+use crate::modules::capture::meter;
+use crate::modules::detector::Detector;
+use crate::modules::protocol::Verdict;
+use crate::DefaultStruct;
+
+use pcap::Capture;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+DefaultStruct! {
+    pub struct OfflineReport {
+        file: String,
+        flows: u64,
+        anomalies: u64,
+        labels: BTreeMap<String, u64>,
+        // Stopped before the end of the file
+        cancelled: bool,
+    }
+}
+
+// One `offline_verdict` event of analysis `id`
+DefaultStruct! {
+    pub struct OfflineVerdict {
+        id: u64,
+        verdict: Verdict,
+    }
+}
+
+// The `offline_report` event that ends analysis `id`, with its report or
+// why there is none
+DefaultStruct! {
+    pub struct OfflineResult {
+        id: u64,
+        report: Option<OfflineReport>,
+        error: Option<String>,
+    }
+}
+
+impl OfflineResult {
+    pub fn new(id: u64, result: Result<OfflineReport, String>) -> Self {
+        let (report, error) = match result {
+            Ok(report) => (Some(report), None),
+            Err(e) => (None, Some(e)),
+        };
+        Self { id, report, error }
+    }
+}
+
+/// Cancel flags of the analyses still running, by id.
+#[derive(Debug, Default)]
+pub struct Analyses {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<u64, Arc<AtomicBool>>>,
+}
+
+impl Analyses {
+    /// Registers a new analysis. Ids are never reused.
+    pub fn start(&self) -> (u64, Arc<AtomicBool>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.running.lock().unwrap().insert(id, Arc::clone(&cancel));
+        (id, cancel)
+    }
+
+    /// Asks analysis `id` to stop. `false` if it isn't running.
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.running.lock().unwrap().get(&id) {
+            Some(cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn finish(&self, id: u64) {
+        self.running.lock().unwrap().remove(&id);
+    }
+}
+
+pub fn check_capture_file(path: &Path) -> Result<(), String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    match ext.as_deref() {
+        Some("pcap") | Some("pcapng") | Some("cap") => {}
+        _ => return Err(format!("{} is not a .pcap or .pcapng file", path.display())),
+    }
+
+    if !path.is_file() {
+        return Err(format!("{} does not exist", path.display()));
+    }
+
+    Ok(())
+}
+
+/// Replays a saved capture through flow metering and the detector, calling
+/// `on_verdict` for each flow in the order flows finish. Verdicts carry the
+/// capture's file name in place of the interface name. The report only
+/// counts them, so memory stays flat however large the capture is.
+/// Setting `cancel` stops the replay; flows still open are classified.
+pub fn analyze_pcap(
+    path: &Path,
+    detector: &Detector,
+    cancel: &AtomicBool,
+    mut on_verdict: impl FnMut(&Verdict),
+) -> Result<OfflineReport, String> {
+    check_capture_file(path)?;
+
+    if !detector.is_ready() {
+        return Err("No detection model could be loaded".into());
+    }
+
+    // libpcap reads both classic pcap and pcapng
+    let mut cap = Capture::from_file(path)
+        .map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+
+    let file = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let mut report = OfflineReport {
+        file: file.clone(),
+        flows: 0,
+        anomalies: 0,
+        labels: BTreeMap::new(),
+        cancelled: false,
+    };
+
+    meter(&mut cap, cancel, |flow| {
+        report.flows += 1;
+
+        if let Some(verdict) = detector.classify(&file, &flow) {
+            if verdict.is_anomaly {
+                report.anomalies += 1;
+            }
+            *report.labels.entry(verdict.label.clone()).or_default() += 1;
+
+            on_verdict(&verdict);
+        }
+    })?;
+
+    report.cancelled = cancel.load(Ordering::Relaxed);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analyses_cancel_only_their_own_flag() {
+        let analyses = Analyses::default();
+        let (first, first_cancel) = analyses.start();
+        let (second, second_cancel) = analyses.start();
+        assert_ne!(first, second);
+
+        assert!(analyses.cancel(second));
+        assert!(second_cancel.load(Ordering::Relaxed));
+        assert!(!first_cancel.load(Ordering::Relaxed));
+
+        analyses.finish(first);
+        assert!(!analyses.cancel(first));
+        assert!(!analyses.cancel(second + 1));
+    }
+}
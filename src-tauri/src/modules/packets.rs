@@ -0,0 +1,238 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+// ---- pcap link-layer types ----
+pub const LINKTYPE_NULL: i32 = 0;
+pub const LINKTYPE_ETHERNET: i32 = 1;
+pub const LINKTYPE_RAW: i32 = 101;
+pub const LINKTYPE_LINUX_SLL: i32 = 113;
+pub const LINKTYPE_LINUX_SLL2: i32 = 276;
+
+// ---- IP protocol numbers ----
+pub const PROTO_ICMP: u8 = 1;
+pub const PROTO_TCP: u8 = 6;
+pub const PROTO_UDP: u8 = 17;
+pub const PROTO_ICMPV6: u8 = 58;
+
+// ---- TCP flag bits ----
+pub const TCP_FIN: u8 = 0x01;
+pub const TCP_SYN: u8 = 0x02;
+pub const TCP_RST: u8 = 0x04;
+pub const TCP_PSH: u8 = 0x08;
+pub const TCP_ACK: u8 = 0x10;
+pub const TCP_URG: u8 = 0x20;
+pub const TCP_ECE: u8 = 0x40;
+pub const TCP_CWR: u8 = 0x80;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+pub const ETHERTYPE_ARP: u16 = 0x0806;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88A8;
+
+/// A decoded IP packet with the fields the flow meter needs.
+/// `payload` is the captured transport payload and can be shorter than
+/// `payload_len` when the capture was truncated by the snaplen.
+#[derive(Debug, Clone)]
+pub struct Packet<'a> {
+    pub timestamp_us: i64,
+    pub src_ip: IpAddr,
+    pub dst_ip: IpAddr,
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub protocol: u8,
+    pub header_len: u32,
+    pub payload_len: u32,
+    pub tcp_flags: u8,
+    pub tcp_window: u16,
+    pub payload: &'a [u8],
+}
+
+impl Packet<'_> {
+    pub fn has_flag(&self, flag: u8) -> bool {
+        self.tcp_flags & flag != 0
+    }
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([*data.get(at)?, *data.get(at + 1)?]))
+}
+
+/// Strips the link-layer header. Returns the ethertype of what it carried
+/// and the network-layer bytes.
+pub fn link_payload(linktype: i32, data: &[u8]) -> Option<(u16, &[u8])> {
+    let payload = match linktype {
+        LINKTYPE_ETHERNET => parse_ethernet(data)?,
+        LINKTYPE_LINUX_SLL => (read_u16(data, 14)?, data.get(16..)?),
+        LINKTYPE_LINUX_SLL2 => (read_u16(data, 0)?, data.get(20..)?),
+        LINKTYPE_NULL => {
+            // BSD loopback: 4-byte address family in host byte order
+            let family = u32::from_ne_bytes(data.get(0..4)?.try_into().ok()?);
+            let ethertype = match family {
+                2 => ETHERTYPE_IPV4,
+                24 | 28 | 30 => ETHERTYPE_IPV6,
+                _ => return None,
+            };
+            (ethertype, data.get(4..)?)
+        }
+        // Raw IP (101 is the pcap value, 12 and 14 are legacy DLT values)
+        LINKTYPE_RAW | 12 | 14 => match data.first()? >> 4 {
+            4 => (ETHERTYPE_IPV4, data),
+            6 => (ETHERTYPE_IPV6, data),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(payload)
+}
+
+pub fn parse_packet(linktype: i32, data: &[u8], timestamp_us: i64) -> Option<Packet<'_>> {
+    let (ethertype, l3) = link_payload(linktype, data)?;
+
+    match ethertype {
+        ETHERTYPE_IPV4 => parse_ipv4(l3, timestamp_us),
+        ETHERTYPE_IPV6 => parse_ipv6(l3, timestamp_us),
+        _ => None,
+    }
+}
+
+fn parse_ethernet(data: &[u8]) -> Option<(u16, &[u8])> {
+    let mut offset = 12;
+    let mut ethertype = read_u16(data, offset)?;
+
+    // ---- Skip 802.1Q / 802.1ad tags ----
+    while ethertype == ETHERTYPE_VLAN || ethertype == ETHERTYPE_QINQ {
+        offset += 4;
+        ethertype = read_u16(data, offset)?;
+    }
+
+    Some((ethertype, data.get(offset + 2..)?))
+}
+
+fn parse_ipv4(data: &[u8], timestamp_us: i64) -> Option<Packet<'_>> {
+    let ihl = ((*data.first()? & 0x0F) as usize) * 4;
+    if ihl < 20 || data.len() < ihl {
+        return None;
+    }
+
+    // Non-first fragments carry no transport header
+    let fragment_offset = read_u16(data, 6)? & 0x1FFF;
+    if fragment_offset != 0 {
+        return None;
+    }
+
+    let total_len = (read_u16(data, 2)? as usize).max(ihl);
+    let protocol = data[9];
+    let src = Ipv4Addr::new(data[12], data[13], data[14], data[15]);
+    let dst = Ipv4Addr::new(data[16], data[17], data[18], data[19]);
+
+    // Trim Ethernet padding, but keep truncated captures
+    let end = total_len.min(data.len());
+
+    parse_transport(
+        protocol,
+        &data[ihl..end],
+        total_len - ihl,
+        IpAddr::V4(src),
+        IpAddr::V4(dst),
+        timestamp_us,
+    )
+}
+
+fn parse_ipv6(data: &[u8], timestamp_us: i64) -> Option<Packet<'_>> {
+    if data.len() < 40 || data[0] >> 4 != 6 {
+        return None;
+    }
+
+    let payload_len = read_u16(data, 4)? as usize;
+    let mut next = data[6];
+    let src: [u8; 16] = data[8..24].try_into().ok()?;
+    let dst: [u8; 16] = data[24..40].try_into().ok()?;
+
+    // ---- Walk extension headers ----
+    let mut offset = 40;
+    loop {
+        match next {
+            0 | 43 | 60 => {
+                next = *data.get(offset)?;
+                offset += (*data.get(offset + 1)? as usize + 1) * 8;
+            }
+            44 => {
+                // Fragment header: only the first fragment has ports
+                let frag = read_u16(data, offset + 2)? >> 3;
+                if frag != 0 {
+                    return None;
+                }
+                next = *data.get(offset)?;
+                offset += 8;
+            }
+            51 => {
+                next = *data.get(offset)?;
+                offset += (*data.get(offset + 1)? as usize + 2) * 4;
+            }
+            _ => break,
+        }
+    }
+
+    let total_len = 40 + payload_len;
+    if offset > total_len {
+        return None;
+    }
+    let end = total_len.min(data.len());
+
+    parse_transport(
+        next,
+        data.get(offset..end)?,
+        total_len - offset,
+        IpAddr::V6(Ipv6Addr::from(src)),
+        IpAddr::V6(Ipv6Addr::from(dst)),
+        timestamp_us,
+    )
+}
+
+fn parse_transport(
+    protocol: u8,
+    data: &[u8],
+    wire_len: usize,
+    src_ip: IpAddr,
+    dst_ip: IpAddr,
+    timestamp_us: i64,
+) -> Option<Packet<'_>> {
+    let mut packet = Packet {
+        timestamp_us,
+        src_ip,
+        dst_ip,
+        src_port: 0,
+        dst_port: 0,
+        protocol,
+        header_len: 0,
+        payload_len: 0,
+        tcp_flags: 0,
+        tcp_window: 0,
+        payload: &[],
+    };
+
+    let header_len = match protocol {
+        PROTO_TCP => {
+            packet.src_port = read_u16(data, 0)?;
+            packet.dst_port = read_u16(data, 2)?;
+            packet.tcp_flags = *data.get(13)?;
+            packet.tcp_window = read_u16(data, 14)?;
+            ((*data.get(12)? >> 4) as usize) * 4
+        }
+        PROTO_UDP => {
+            packet.src_port = read_u16(data, 0)?;
+            packet.dst_port = read_u16(data, 2)?;
+            8
+        }
+        PROTO_ICMP | PROTO_ICMPV6 => 8,
+        _ => 0,
+    };
+
+    let header_len = header_len.min(wire_len);
+    packet.header_len = header_len as u32;
+    packet.payload_len = (wire_len - header_len) as u32;
+    packet.payload = data.get(header_len..).unwrap_or(&[]);
+
+    Some(packet)
+}
@@ -0,0 +1,241 @@
+use crate::modules::arp;
+use crate::modules::dns;
+use crate::modules::notifier::Notifier;
+use crate::modules::protocol::{ModelSource, Verdict, WorkerMessage};
+use crate::modules::supervisor::WorkerOutput;
+use crate::modules::tls;
+use crate::{handle_anomaly, AppState, DefaultStruct};
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ---------------------------------------------------------------------
+// CAPTURE MESSAGE PIPELINE
+//
+//   live captures ─▶ state updater ─▶ notifier
+//
+// Each arrow is a bounded queue. Captures hand over typed messages, so
+// nothing needs parsing. A full update queue sheds routine traffic (benign
+// verdicts, raw packets); the rest makes the capture thread wait, which
+// then falls behind in libpcap's buffer, so no anomaly or health report is
+// ever lost. Past the state updater, only UI notifications may be dropped.
+// ---------------------------------------------------------------------
+pub const QUEUE_CAPACITY: usize = 1024;
+
+// Flow history is written one transaction per batch
+const FLOW_BATCH: usize = 256;
+const FLOW_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+#[derive(Debug, Default)]
+pub struct PipelineCounters {
+    messages_in: AtomicU64,
+    // Times a capture found the update queue full and had to wait
+    messages_blocked: AtomicU64,
+    forwarded: AtomicU64,
+    verdicts_dropped: AtomicU64,
+    applied: AtomicU64,
+    notifications_queued: AtomicU64,
+    notifications_dropped: AtomicU64,
+    notified: AtomicU64,
+}
+
+DefaultStruct! {
+    pub struct PipelineStats {
+        capacity: usize,
+        messages_in: u64,
+        messages_blocked: u64,
+        verdicts_dropped: u64,
+        updates_pending: u64,
+        applied: u64,
+        notifications_dropped: u64,
+        notifications_pending: u64,
+        notified: u64,
+    }
+}
+
+fn bump(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+impl PipelineCounters {
+    pub fn stats(&self) -> PipelineStats {
+        let get = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+
+        PipelineStats {
+            capacity: QUEUE_CAPACITY,
+            messages_in: get(&self.messages_in),
+            messages_blocked: get(&self.messages_blocked),
+            verdicts_dropped: get(&self.verdicts_dropped),
+            updates_pending: get(&self.forwarded).saturating_sub(get(&self.applied)),
+            applied: get(&self.applied),
+            notifications_dropped: get(&self.notifications_dropped),
+            notifications_pending: get(&self.notifications_queued)
+                .saturating_sub(get(&self.notified)),
+            notified: get(&self.notified),
+        }
+    }
+}
+
+/// Entry point of the pipeline, shared by every live capture.
+/// Counters live in `state.pipeline`.
+#[derive(Clone)]
+pub struct Pipeline {
+    updates: SyncSender<WorkerMessage>,
+    state: Arc<AppState>,
+}
+
+impl Pipeline {
+    /// Spawns the state updater and notifier threads.
+    pub fn start(notifier: Arc<dyn Notifier>, state: Arc<AppState>) -> Self {
+        let (update_tx, update_rx) = sync_channel(QUEUE_CAPACITY);
+        let (notify_tx, notify_rx) = sync_channel(QUEUE_CAPACITY);
+
+        let updater_state = Arc::clone(&state);
+        thread::spawn(move || update_stage(&updater_state, update_rx, notify_tx));
+
+        let notifier_state = Arc::clone(&state);
+        thread::spawn(move || notify_stage(&notifier_state, notifier, notify_rx));
+
+        Self {
+            updates: update_tx,
+            state,
+        }
+    }
+
+    /// Queues one message a live capture produced, on behalf of `output`.
+    /// Routine traffic is dropped while the state updater is behind,
+    /// anything else waits for room.
+    pub fn send(&self, output: &WorkerOutput, message: WorkerMessage) {
+        let counters = &self.state.pipeline;
+        bump(&counters.messages_in);
+        output.record(&message);
+
+        let droppable = match &message {
+            WorkerMessage::Verdict(verdict) => !verdict.is_anomaly,
+            WorkerMessage::Dns(_) | WorkerMessage::Arp(_) => true,
+            _ => false,
+        };
+
+        let sent = match self.updates.try_send(message) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) if droppable => {
+                bump(&counters.verdicts_dropped);
+                return;
+            }
+            Err(TrySendError::Full(message)) => {
+                bump(&counters.messages_blocked);
+                self.updates.send(message).is_ok()
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        };
+        if sent {
+            bump(&counters.forwarded);
+        }
+    }
+}
+
+fn update_stage(
+    state: &AppState,
+    updates: Receiver<WorkerMessage>,
+    notifications: SyncSender<WorkerMessage>,
+) {
+    let counters = &state.pipeline;
+    let mut flows: Vec<Verdict> = Vec::with_capacity(FLOW_BATCH);
+    let mut flushed_at = Instant::now();
+
+    loop {
+        if flows.len() >= FLOW_BATCH || flushed_at.elapsed() >= FLOW_FLUSH_INTERVAL {
+            persist_flows(state, &mut flows);
+            flushed_at = Instant::now();
+        }
+
+        let mut message = match updates.recv_timeout(FLOW_FLUSH_INTERVAL) {
+            Ok(message) => message,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        // Scans, beacons, tunnels and spoofing only show across flows or
+        // messages, so everything feeds the detectors
+        let mut derived = Vec::new();
+        match &mut message {
+            WorkerMessage::Verdict(verdict) => {
+                let (scan, beacon, blocklist) = {
+                    let config = state.config.read().unwrap();
+                    (config.scan.clone(), config.beacon.clone(), config.tls.blocklist.clone())
+                };
+
+                if let Some(info) = verdict.tls.as_mut() {
+                    info.blocklisted = state.tls_blocklist.lookup(blocklist.as_deref(), info);
+                }
+
+                handle_anomaly(state, verdict);
+                // Only model verdicts are flows, the detectors' alerts are kept as alerts
+                if matches!(verdict.model, Some(ModelSource::Xgb | ModelSource::Lstm)) {
+                    flows.push(verdict.clone());
+                }
+
+                derived = state.scans.observe(verdict, &scan);
+                derived.extend(state.beacons.observe(verdict, &beacon));
+                derived.extend(tls::blocklist_alert(verdict));
+            }
+            WorkerMessage::Dns(packet) => {
+                if let Some(record) = dns::decode(packet) {
+                    let config = state.config.read().unwrap().dns.clone();
+                    derived = state.dns.observe(&record, &config);
+                    state.dns_log.record(record);
+                }
+            }
+            WorkerMessage::Arp(packet) => {
+                if let Some(frame) = arp::decode(packet) {
+                    let config = state.config.read().unwrap().arp.clone();
+                    derived = state.arp.observe(&frame, &config);
+                }
+            }
+            _ => {}
+        }
+        for alert in &mut derived {
+            handle_anomaly(state, alert);
+        }
+        bump(&counters.applied);
+
+        let raw = (!matches!(message, WorkerMessage::Dns(_) | WorkerMessage::Arp(_)))
+            .then_some(message);
+        let messages = raw
+            .into_iter()
+            .chain(derived.into_iter().map(WorkerMessage::Verdict));
+        for message in messages {
+            // The UI is best effort; the state above is already up to date
+            match notifications.try_send(message) {
+                Ok(()) => bump(&counters.notifications_queued),
+                Err(TrySendError::Full(_)) => bump(&counters.notifications_dropped),
+                Err(TrySendError::Disconnected(_)) => {
+                    persist_flows(state, &mut flows);
+                    return;
+                }
+            }
+        }
+    }
+
+    persist_flows(state, &mut flows);
+}
+
+fn persist_flows(state: &AppState, flows: &mut Vec<Verdict>) {
+    if flows.is_empty() {
+        return;
+    }
+    if let Some(store) = state.store.read().unwrap().clone() {
+        store.insert_flows(flows).inspect_err(|e| eprintln!("{e}")).ok();
+    }
+    flows.clear();
+}
+
+fn notify_stage(state: &AppState, notifier: Arc<dyn Notifier>, notifications: Receiver<WorkerMessage>) {
+    for message in notifications {
+        notifier.worker_message(message);
+        bump(&state.pipeline.notified);
+    }
+}
@@ -0,0 +1,551 @@
+use crate::modules::alerts::Alert;
+use crate::modules::arp::ArpBinding;
+use crate::modules::deltas::{NetworkChange, NetworkDelta, NetworkSnapshot};
+use crate::modules::dns::DnsRecord;
+use crate::modules::helpers::{from_hex, to_hex};
+use crate::modules::protocol::{Verdict, WorkerMessage};
+use crate::modules::storage::{FlowRecord, InterfaceSnapshot};
+use crate::{Connection, IPInfo, NetworkInfo, ProcessInfo};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use std::str::FromStr;
+
+pub const SALT_FILE: &str = "privacy_salt";
+
+// ---------------------------------------------------------------------
+// ADDRESS CLASSIFICATION
+//
+// Prefix tables from the IANA special-purpose registries
+// (RFC 6890 and successors). First match wins, so more specific
+// prefixes come before the blocks that contain them.
+// ---------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddrScope {
+    Unspecified,
+    Loopback,
+    LinkLocal,
+    Private,
+    SharedCgnat,
+    Documentation,
+    Benchmarking,
+    Multicast,
+    Broadcast,
+    Reserved,
+    Public,
+}
+
+const V4_TABLE: &[(Ipv4Addr, u8, AddrScope)] = &[
+    (Ipv4Addr::new(0, 0, 0, 0), 8, AddrScope::Unspecified), // RFC 1122
+    (Ipv4Addr::new(10, 0, 0, 0), 8, AddrScope::Private), // RFC 1918
+    (Ipv4Addr::new(100, 64, 0, 0), 10, AddrScope::SharedCgnat), // RFC 6598
+    (Ipv4Addr::new(127, 0, 0, 0), 8, AddrScope::Loopback), // RFC 1122
+    (Ipv4Addr::new(169, 254, 0, 0), 16, AddrScope::LinkLocal), // RFC 3927
+    (Ipv4Addr::new(172, 16, 0, 0), 12, AddrScope::Private), // RFC 1918
+    (Ipv4Addr::new(192, 0, 0, 0), 24, AddrScope::Reserved), // RFC 6890
+    (Ipv4Addr::new(192, 0, 2, 0), 24, AddrScope::Documentation), // RFC 5737
+    (Ipv4Addr::new(192, 168, 0, 0), 16, AddrScope::Private), // RFC 1918
+    (Ipv4Addr::new(198, 18, 0, 0), 15, AddrScope::Benchmarking), // RFC 2544
+    (Ipv4Addr::new(198, 51, 100, 0), 24, AddrScope::Documentation), // RFC 5737
+    (Ipv4Addr::new(203, 0, 113, 0), 24, AddrScope::Documentation), // RFC 5737
+    (Ipv4Addr::new(224, 0, 0, 0), 4, AddrScope::Multicast), // RFC 5771
+    (Ipv4Addr::new(255, 255, 255, 255), 32, AddrScope::Broadcast), // RFC 919
+    (Ipv4Addr::new(240, 0, 0, 0), 4, AddrScope::Reserved), // RFC 1112
+];
+
+const V6_TABLE: &[(Ipv6Addr, u8, AddrScope)] = &[
+    (Ipv6Addr::UNSPECIFIED, 128, AddrScope::Unspecified), // RFC 4291
+    (Ipv6Addr::LOCALHOST, 128, AddrScope::Loopback), // RFC 4291
+    (Ipv6Addr::new(0x100, 0, 0, 0, 0, 0, 0, 0), 64, AddrScope::Reserved), // RFC 6666 discard
+    (Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32, AddrScope::Documentation), // RFC 3849
+    (Ipv6Addr::new(0x2001, 0x2, 0, 0, 0, 0, 0, 0), 48, AddrScope::Benchmarking), // RFC 5180
+    (Ipv6Addr::new(0xfc00, 0, 0, 0, 0, 0, 0, 0), 7, AddrScope::Private), // RFC 4193 ULA
+    (Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0), 10, AddrScope::LinkLocal), // RFC 4291
+    (Ipv6Addr::new(0xff00, 0, 0, 0, 0, 0, 0, 0), 8, AddrScope::Multicast), // RFC 4291
+];
+
+fn v4_in(ip: Ipv4Addr, net: Ipv4Addr, len: u8) -> bool {
+    let mask = u32::MAX.checked_shl(32 - len as u32).unwrap_or(0);
+    (u32::from(ip) & mask) == (u32::from(net) & mask)
+}
+
+fn v6_in(ip: Ipv6Addr, net: Ipv6Addr, len: u8) -> bool {
+    let mask = u128::MAX.checked_shl(128 - len as u32).unwrap_or(0);
+    (u128::from(ip) & mask) == (u128::from(net) & mask)
+}
+
+pub fn classify(ip: IpAddr) -> AddrScope {
+    match ip {
+        IpAddr::V4(v4) => V4_TABLE
+            .iter()
+            .find(|(net, len, _)| v4_in(v4, *net, *len))
+            .map_or(AddrScope::Public, |(_, _, scope)| *scope),
+        IpAddr::V6(v6) => {
+            // IPv4-mapped (::ffff:0:0/96) takes the scope of the embedded address
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return classify(IpAddr::V4(v4));
+            }
+
+            V6_TABLE
+                .iter()
+                .find(|(net, len, _)| v6_in(v6, *net, *len))
+                .map_or(AddrScope::Public, |(_, _, scope)| *scope)
+        }
+    }
+}
+
+impl AddrScope {
+    /// Whether an address in this scope can identify a user or site
+    /// outside the local network. Only these are masked.
+    pub fn is_identifying(self) -> bool {
+        matches!(self, AddrScope::Public | AddrScope::SharedCgnat)
+    }
+}
+
+// ---------------------------------------------------------------------
+// POLICY
+// ---------------------------------------------------------------------
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyPolicy {
+    ShowAll,
+    #[default]
+    MaskPublic,
+    Hash,
+    Truncate,
+}
+
+impl FromStr for PrivacyPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
+            "show_all" => Ok(PrivacyPolicy::ShowAll),
+            "mask_public" => Ok(PrivacyPolicy::MaskPublic),
+            "hash" => Ok(PrivacyPolicy::Hash),
+            "truncate" => Ok(PrivacyPolicy::Truncate),
+            other => Err(format!(
+                "Unknown privacy policy '{other}' (show_all, mask_public, hash, truncate)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PrivacyPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PrivacyPolicy::ShowAll => "show_all",
+            PrivacyPolicy::MaskPublic => "mask_public",
+            PrivacyPolicy::Hash => "hash",
+            PrivacyPolicy::Truncate => "truncate",
+        })
+    }
+}
+
+/// How IPs and MACs leave the backend. Addresses are stored unmasked; every
+/// command, event and log line goes through `Privacy::ip` and `Privacy::mac`.
+#[derive(Clone)]
+pub struct Privacy {
+    policy: PrivacyPolicy,
+    salt: Vec<u8>,
+}
+
+impl fmt::Debug for Privacy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the salt
+        f.debug_struct("Privacy").field("policy", &self.policy).finish()
+    }
+}
+
+impl Default for Privacy {
+    fn default() -> Self {
+        Self {
+            policy: PrivacyPolicy::default(),
+            salt: rand::random::<[u8; 32]>().to_vec(),
+        }
+    }
+}
+
+impl Privacy {
+    /// Uses the salt in `salt_path`, creating it on first run so hashes stay
+    /// stable across restarts of the same install.
+    pub fn load(policy: PrivacyPolicy, salt_path: &Path) -> Self {
+        let salt = match fs::read_to_string(salt_path).ok().and_then(|s| from_hex(&s)) {
+            Some(salt) if !salt.is_empty() => salt,
+            _ => {
+                let salt = rand::random::<[u8; 32]>().to_vec();
+                if let Some(parent) = salt_path.parent() {
+                    let _ = fs::create_dir_all(parent);
+                }
+                fs::write(salt_path, to_hex(&salt))
+                    .inspect_err(|e| eprintln!("Could not save privacy salt: {e}"))
+                    .ok();
+                salt
+            }
+        };
+
+        Self { policy, salt }
+    }
+
+    pub fn set_policy(&mut self, policy: PrivacyPolicy) {
+        self.policy = policy;
+    }
+
+    fn hash(&self, addr: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.salt);
+        hasher.update(addr.as_bytes());
+
+        format!("anon-{}", &to_hex(&hasher.finalize())[..12])
+    }
+
+    /// Applies the policy to one address. Strings that are not IPs pass through.
+    pub fn ip(&self, ip: &str) -> String {
+        // IPv4-mapped IPv6 is masked as the IPv4 address it carries
+        let addr = match ip.parse::<IpAddr>() {
+            Ok(addr) => addr.to_canonical(),
+            Err(_) => return ip.to_string(),
+        };
+
+        if self.policy == PrivacyPolicy::ShowAll || !classify(addr).is_identifying() {
+            return addr.to_string();
+        }
+
+        match (self.policy, addr) {
+            (PrivacyPolicy::Hash, _) => self.hash(&addr.to_string()),
+            (PrivacyPolicy::Truncate, IpAddr::V4(v4)) => {
+                let o = v4.octets();
+                format!("{}.{}.{}.0/24", o[0], o[1], o[2])
+            }
+            (PrivacyPolicy::Truncate, IpAddr::V6(v6)) => {
+                let s = v6.segments();
+                format!("{:x}:{:x}:{:x}::/48", s[0], s[1], s[2])
+            }
+            (_, IpAddr::V4(v4)) => {
+                let o = v4.octets();
+                format!("{}.{}.x.x", o[0], o[1])
+            }
+            (_, IpAddr::V6(v6)) => {
+                let s = v6.segments();
+                format!("{:x}:{:x}:{:x}:x:x:x:x:x", s[0], s[1], s[2])
+            }
+        }
+    }
+
+    pub fn ips(&self, ips: &[String]) -> Vec<String> {
+        ips.iter().map(|ip| self.ip(ip)).collect()
+    }
+
+    /// Applies the policy to one MAC address. A burned-in MAC names the
+    /// device wherever it goes, so it is masked like a public IP, down to
+    /// its vendor prefix. Group and locally administered (randomised)
+    /// addresses identify nothing and pass through, as do non-MACs.
+    pub fn mac(&self, mac: &str) -> String {
+        let octets: Vec<&str> = mac.split(':').collect();
+        // Bit 0 of the first octet marks group addresses, bit 1 local ones
+        let identifying = octets.len() == 6
+            && u8::from_str_radix(octets[0], 16).is_ok_and(|first| first & 0x03 == 0);
+
+        if self.policy == PrivacyPolicy::ShowAll || !identifying {
+            return mac.to_string();
+        }
+
+        match self.policy {
+            PrivacyPolicy::Hash => self.hash(&mac.to_lowercase()),
+            _ => format!("{}:xx:xx:xx", octets[..3].join(":")),
+        }
+    }
+
+    pub fn macs(&self, macs: &[String]) -> Vec<String> {
+        macs.iter().map(|mac| self.mac(mac)).collect()
+    }
+}
+
+/// Policy from `SENTINELAI_PRIVACY`, when set. Overrides the config file.
+pub fn policy_from_env() -> Option<PrivacyPolicy> {
+    std::env::var("SENTINELAI_PRIVACY").ok().and_then(|v| {
+        v.parse()
+            .inspect_err(|e: &String| eprintln!("SENTINELAI_PRIVACY: {e}"))
+            .ok()
+    })
+}
+
+// ---- Masking at the edges ----
+
+pub trait Redact {
+    fn redact(self, privacy: &Privacy) -> Self;
+}
+
+impl Redact for IPInfo {
+    fn redact(self, privacy: &Privacy) -> Self {
+        IPInfo {
+            ipv4: self.ipv4.map(|ip| privacy.ip(&ip)),
+            ipv6: self.ipv6.map(|ip| privacy.ip(&ip)),
+            ipv4_addrs: privacy.ips(&self.ipv4_addrs),
+            ipv6_addrs: privacy.ips(&self.ipv6_addrs),
+        }
+    }
+}
+
+impl Redact for Connection {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.local_addr = privacy.ip(&self.local_addr);
+        self.remote_addr = self.remote_addr.map(|ip| privacy.ip(&ip));
+        self
+    }
+}
+
+impl Redact for NetworkInfo {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.ip_info = self.ip_info.redact(privacy);
+        self.connections = self.connections.redact(privacy);
+        self
+    }
+}
+
+impl Redact for NetworkChange {
+    fn redact(self, privacy: &Privacy) -> Self {
+        match self {
+            NetworkChange::Add { network } => NetworkChange::Add {
+                network: network.redact(privacy),
+            },
+            NetworkChange::Update { network } => NetworkChange::Update {
+                network: network.redact(privacy),
+            },
+            NetworkChange::Remove { id } => NetworkChange::Remove { id },
+        }
+    }
+}
+
+impl Redact for NetworkDelta {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.changes = self.changes.redact(privacy);
+        self
+    }
+}
+
+impl Redact for NetworkSnapshot {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.networks = self.networks.redact(privacy);
+        self
+    }
+}
+
+impl Redact for ProcessInfo {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.connections = self.connections.redact(privacy);
+        self
+    }
+}
+
+impl Redact for Alert {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.src_ip = privacy.ip(&self.src_ip);
+        self.dst_ip = privacy.ip(&self.dst_ip);
+        self.macs = privacy.macs(&self.macs);
+        self
+    }
+}
+
+impl Redact for DnsRecord {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.client = privacy.ip(&self.client);
+        self.server = privacy.ip(&self.server);
+        // A and AAAA answers are addresses too
+        self.answers = privacy.ips(&self.answers);
+        self
+    }
+}
+
+impl Redact for ArpBinding {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.ip = privacy.ip(&self.ip);
+        self.mac = privacy.mac(&self.mac);
+        self.previous_mac = self.previous_mac.map(|mac| privacy.mac(&mac));
+        self
+    }
+}
+
+impl Redact for FlowRecord {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.src_ip = privacy.ip(&self.src_ip);
+        self.dst_ip = privacy.ip(&self.dst_ip);
+        self
+    }
+}
+
+impl Redact for InterfaceSnapshot {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.ipv4 = self.ipv4.map(|ip| privacy.ip(&ip));
+        self.ipv6 = self.ipv6.map(|ip| privacy.ip(&ip));
+        self
+    }
+}
+
+impl Redact for Verdict {
+    fn redact(mut self, privacy: &Privacy) -> Self {
+        self.flow.src_ip = privacy.ip(&self.flow.src_ip);
+        self.flow.dst_ip = privacy.ip(&self.flow.dst_ip);
+        self.macs = privacy.macs(&self.macs);
+        self
+    }
+}
+
+impl Redact for WorkerMessage {
+    fn redact(self, privacy: &Privacy) -> Self {
+        match self {
+            WorkerMessage::Verdict(verdict) => WorkerMessage::Verdict(verdict.redact(privacy)),
+            other => other,
+        }
+    }
+}
+
+impl<T: Redact> Redact for Vec<T> {
+    fn redact(self, privacy: &Privacy) -> Self {
+        self.into_iter().map(|item| item.redact(privacy)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn privacy(policy: PrivacyPolicy) -> Privacy {
+        Privacy {
+            policy,
+            salt: b"test salt".to_vec(),
+        }
+    }
+
+    fn scope(ip: &str) -> AddrScope {
+        classify(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn rfc1918_172_block_is_only_the_slash_12() {
+        assert_eq!(scope("172.15.255.255"), AddrScope::Public);
+        assert_eq!(scope("172.16.0.0"), AddrScope::Private);
+        assert_eq!(scope("172.31.255.255"), AddrScope::Private);
+        assert_eq!(scope("172.32.0.0"), AddrScope::Public);
+    }
+
+    #[test]
+    fn cgnat_and_link_local_boundaries() {
+        assert_eq!(scope("100.63.255.255"), AddrScope::Public);
+        assert_eq!(scope("100.64.0.0"), AddrScope::SharedCgnat);
+        assert_eq!(scope("100.127.255.255"), AddrScope::SharedCgnat);
+        assert_eq!(scope("100.128.0.0"), AddrScope::Public);
+
+        assert_eq!(scope("169.253.255.255"), AddrScope::Public);
+        assert_eq!(scope("169.254.0.1"), AddrScope::LinkLocal);
+        assert_eq!(scope("169.254.255.255"), AddrScope::LinkLocal);
+        assert_eq!(scope("169.255.0.0"), AddrScope::Public);
+    }
+
+    #[test]
+    fn ipv6_link_local_and_unique_local_boundaries() {
+        assert_eq!(scope("fe80::1"), AddrScope::LinkLocal);
+        assert_eq!(scope("febf:ffff::1"), AddrScope::LinkLocal);
+        assert_eq!(scope("fec0::1"), AddrScope::Public);
+
+        assert_eq!(scope("fbff::1"), AddrScope::Public);
+        assert_eq!(scope("fc00::1"), AddrScope::Private);
+        assert_eq!(scope("fdff:ffff::1"), AddrScope::Private);
+        assert_eq!(scope("fe00::1"), AddrScope::Public);
+    }
+
+    #[test]
+    fn limited_broadcast_is_matched_before_class_e() {
+        assert_eq!(scope("255.255.255.255"), AddrScope::Broadcast);
+        assert_eq!(scope("255.255.255.254"), AddrScope::Reserved);
+        assert_eq!(scope("240.0.0.1"), AddrScope::Reserved);
+        assert_eq!(scope("239.255.255.255"), AddrScope::Multicast);
+    }
+
+    #[test]
+    fn ipv4_mapped_ipv6_follows_the_embedded_address() {
+        assert_eq!(scope("::ffff:10.0.0.1"), AddrScope::Private);
+        assert_eq!(scope("::ffff:8.8.8.8"), AddrScope::Public);
+
+        let mask = privacy(PrivacyPolicy::MaskPublic);
+        assert_eq!(mask.ip("::ffff:8.8.8.8"), "8.8.x.x");
+        assert_eq!(mask.ip("::ffff:192.168.1.1"), "192.168.1.1");
+
+        let hash = privacy(PrivacyPolicy::Hash);
+        assert_eq!(hash.ip("::ffff:8.8.8.8"), hash.ip("8.8.8.8"));
+    }
+
+    #[test]
+    fn policies_format_identifying_addresses() {
+        let cases = [
+            (PrivacyPolicy::ShowAll, "8.8.8.8", "2606:4700::1"),
+            (PrivacyPolicy::MaskPublic, "8.8.x.x", "2606:4700:0:x:x:x:x:x"),
+            (PrivacyPolicy::Truncate, "8.8.8.0/24", "2606:4700:0::/48"),
+        ];
+        for (policy, v4, v6) in cases {
+            let privacy = privacy(policy);
+            assert_eq!(privacy.ip("8.8.8.8"), v4, "{policy}");
+            assert_eq!(privacy.ip("2606:4700::1"), v6, "{policy}");
+            // Local addresses and non-addresses are never touched
+            assert_eq!(privacy.ip("192.168.1.10"), "192.168.1.10", "{policy}");
+            assert_eq!(privacy.ip("fe80::1"), "fe80::1", "{policy}");
+            assert_eq!(privacy.ip("eth0"), "eth0", "{policy}");
+        }
+
+        assert_eq!(privacy(PrivacyPolicy::MaskPublic).ip("100.64.1.2"), "100.64.x.x");
+    }
+
+    #[test]
+    fn policy_names_parse_and_round_trip() {
+        assert_eq!("Mask-Public".parse(), Ok(PrivacyPolicy::MaskPublic));
+        assert_eq!(" hash ".parse(), Ok(PrivacyPolicy::Hash));
+        assert!("mask".parse::<PrivacyPolicy>().is_err());
+
+        for policy in [
+            PrivacyPolicy::ShowAll,
+            PrivacyPolicy::MaskPublic,
+            PrivacyPolicy::Hash,
+            PrivacyPolicy::Truncate,
+        ] {
+            assert_eq!(policy.to_string().parse(), Ok(policy));
+        }
+    }
+
+    #[test]
+    fn hashes_are_stable_per_salt() {
+        let hash = privacy(PrivacyPolicy::Hash);
+        let first = hash.ip("8.8.8.8");
+        assert!(first.starts_with("anon-") && first.len() == 17);
+        assert_eq!(hash.ip("8.8.8.8"), first);
+        assert_ne!(hash.ip("8.8.4.4"), first);
+
+        let other = Privacy {
+            policy: PrivacyPolicy::Hash,
+            salt: b"other salt".to_vec(),
+        };
+        assert_ne!(other.ip("8.8.8.8"), first);
+    }
+
+    #[test]
+    fn only_burned_in_macs_are_masked() {
+        let mask = privacy(PrivacyPolicy::MaskPublic);
+        assert_eq!(mask.mac("00:1a:2b:3c:4d:5e"), "00:1a:2b:xx:xx:xx");
+        // Locally administered, multicast and broadcast
+        assert_eq!(mask.mac("02:1a:2b:3c:4d:5e"), "02:1a:2b:3c:4d:5e");
+        assert_eq!(mask.mac("01:00:5e:00:00:fb"), "01:00:5e:00:00:fb");
+        assert_eq!(mask.mac("ff:ff:ff:ff:ff:ff"), "ff:ff:ff:ff:ff:ff");
+        assert_eq!(mask.mac("not-a-mac"), "not-a-mac");
+
+        let hash = privacy(PrivacyPolicy::Hash);
+        assert_eq!(hash.mac("00:1A:2B:3C:4D:5E"), hash.mac("00:1a:2b:3c:4d:5e"));
+        assert_eq!(
+            privacy(PrivacyPolicy::ShowAll).mac("00:1a:2b:3c:4d:5e"),
+            "00:1a:2b:3c:4d:5e"
+        );
+    }
+}
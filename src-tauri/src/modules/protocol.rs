@@ -0,0 +1,166 @@
+use crate::modules::tls::TlsInfo;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+
+// ---------------------------------------------------------------------
+// CAPTURE MESSAGE PROTOCOL
+//
+// Captures hand these to the notifier directly. Serialized, as in the
+// `get_anomaly` event, they are one JSON object per message:
+//   {"v": 1, "type": "verdict", "iface": "...", "timestamp": "...", ...}
+// Bump PROTOCOL_VERSION on incompatible changes;
+// additive fields must carry #[serde(default)] instead.
+// ---------------------------------------------------------------------
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowTuple {
+    pub src_ip: String,
+    pub src_port: u16,
+    pub dst_ip: String,
+    pub dst_port: u16,
+    pub protocol: u8,
+}
+
+/// Which model produced a verdict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ModelSource {
+    Xgb,
+    Lstm,
+    // Backend detectors, not models
+    Scan,
+    Beacon,
+    Dns,
+    Tls,
+    Arp,
+}
+
+/// TCP flag counts from the flow's initiator to its responder.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TcpFlags {
+    pub syn: u32,
+    pub ack: u32,
+    pub rst: u32,
+    pub fin: u32,
+}
+
+impl TcpFlags {
+    /// The initiator asked for a connection but never acknowledged one.
+    pub fn is_half_open(&self) -> bool {
+        self.syn > 0 && self.ack == 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Verdict {
+    pub iface: String,
+    pub flow: FlowTuple,
+    pub label: String,
+    pub is_anomaly: bool,
+    // Class probability for XGBoost, reconstruction error for the LSTM
+    pub score: f64,
+    #[serde(default)]
+    pub model: Option<ModelSource>,
+    // First packet of the flow, when the worker knows it
+    #[serde(default)]
+    pub flow_start: Option<DateTime<Utc>>,
+    // TCP only
+    #[serde(default)]
+    pub fwd_flags: Option<TcpFlags>,
+    // Both directions, model verdicts only
+    #[serde(default)]
+    pub bytes: Option<u64>,
+    // Packets from the responder, model verdicts only
+    #[serde(default)]
+    pub bwd_packets: Option<u64>,
+    // Interval between connections, beaconing verdicts only
+    #[serde(default)]
+    pub period_secs: Option<f64>,
+    // Offending names, DNS verdicts only
+    #[serde(default)]
+    pub domains: Vec<String>,
+    // MAC addresses involved, ARP verdicts only
+    #[serde(default)]
+    pub macs: Vec<String>,
+    // Handshake metadata, when the flow opened with a ClientHello
+    #[serde(default)]
+    pub tls: Option<Box<TlsInfo>>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heartbeat {
+    pub iface: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerFault {
+    pub iface: String,
+    pub message: String,
+    #[serde(default)]
+    pub fatal: bool,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The payload of one UDP packet to or from port 53, as captured.
+/// Decoded by the state updater, see `dns.rs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsPacket {
+    pub iface: String,
+    pub src_ip: String,
+    pub dst_ip: String,
+    pub payload: Vec<u8>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// One ARP message as captured, without the link-layer header.
+/// Decoded by the state updater, see `arp.rs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArpPacket {
+    pub iface: String,
+    pub payload: Vec<u8>,
+    // The interface's default gateway, when the capture knows it
+    #[serde(default)]
+    pub gateway: Option<Ipv4Addr>,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerStats {
+    pub iface: String,
+    pub flows: u64,
+    pub anomalies: u64,
+    pub errors: u64,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerMessage {
+    Verdict(Verdict),
+    Heartbeat(Heartbeat),
+    Error(WorkerFault),
+    Stats(WorkerStats),
+    Dns(DnsPacket),
+    Arp(ArpPacket),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub v: u32,
+    #[serde(flatten)]
+    pub message: WorkerMessage,
+}
+
+impl Envelope {
+    pub fn new(message: WorkerMessage) -> Self {
+        Self {
+            v: PROTOCOL_VERSION,
+            message,
+        }
+    }
+}
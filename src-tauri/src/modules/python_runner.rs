@@ -1,39 +1,145 @@
+use std::env;
+use std::fmt;
+use std::io;
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
+use std::process::{Command, Stdio};
+
+#[cfg(windows)]
 use std::os::windows::process::CommandExt;
 
+// Keeps helper consoles from flashing up on Windows
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+#[cfg(windows)]
+const PATH_CANDIDATES: &[&str] = &["python.exe", "python3.exe"];
+#[cfg(not(windows))]
+const PATH_CANDIDATES: &[&str] = &["python3", "python"];
+
+#[derive(Debug)]
+pub enum RunnerError {
+    InterpreterNotFound { searched: Vec<PathBuf> },
+    Spawn { python: PathBuf, source: io::Error },
+    ScriptFailed { script: PathBuf, code: Option<i32>, stderr: String },
+}
+
+impl fmt::Display for RunnerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunnerError::InterpreterNotFound { searched } => {
+                write!(f, "No Python interpreter found (searched ")?;
+                for (i, path) in searched.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", path.display())?;
+                }
+                write!(f, " and PATH)")
+            }
+            RunnerError::Spawn { python, source } => {
+                write!(f, "Failed to start {}: {source}", python.display())
+            }
+            RunnerError::ScriptFailed { script, code, stderr } => {
+                let code = code.map_or("signal".to_string(), |c| c.to_string());
+                write!(f, "{} exited with {code}: {}", script.display(), stderr.trim())
+            }
+        }
+    }
+}
+
+impl std::error::Error for RunnerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RunnerError::Spawn { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn embedded_candidates(dir: &Path) -> Vec<PathBuf> {
+    if cfg!(windows) {
+        vec![dir.join("python.exe")]
+    } else {
+        vec![dir.join("bin").join("python3"), dir.join("python3")]
+    }
+}
+
+fn venv_candidates(venv: &Path) -> Vec<PathBuf> {
+    if cfg!(windows) {
+        vec![venv.join("Scripts").join("python.exe")]
+    } else {
+        vec![venv.join("bin").join("python3"), venv.join("bin").join("python")]
+    }
+}
+
+fn search_path() -> Option<PathBuf> {
+    let paths = env::var_os("PATH")?;
+
+    env::split_paths(&paths)
+        .flat_map(|dir| PATH_CANDIDATES.iter().map(move |name| dir.join(name)))
+        .find(|candidate| candidate.is_file())
+}
+
 pub struct PythonRunner {
     python: PathBuf,
 }
 
 impl PythonRunner {
-    pub fn new(dir: &Path) -> Self {
-        Self {
-            python: dir.join("python.exe"),
-        }
+    /// Uses the embedded interpreter in `dir` when bundled, otherwise the venv
+    /// named by `SENTINELAI_VENV` (or an activated `VIRTUAL_ENV`), otherwise PATH.
+    pub fn new(dir: &Path) -> Result<Self, RunnerError> {
+        let venv = env::var_os("SENTINELAI_VENV")
+            .or_else(|| env::var_os("VIRTUAL_ENV"))
+            .map(PathBuf::from);
+
+        Self::discover(dir, venv.as_deref())
     }
 
-    pub fn run(&self, script: impl AsRef<Path>) -> Option<String> {
-        let output = Command::new(&self.python)
-            .arg(script.as_ref())
-            .creation_flags(0x08000000)
-            .stderr(Stdio::piped())
-            .output()
-            .ok()?;
+    pub fn discover(embedded_dir: &Path, venv: Option<&Path>) -> Result<Self, RunnerError> {
+        let mut searched = embedded_candidates(embedded_dir);
+        if let Some(venv) = venv {
+            searched.extend(venv_candidates(venv));
+        }
+
+        let found = searched.iter().find(|candidate| candidate.is_file()).cloned();
 
-        Some(String::from_utf8_lossy(&output.stdout).to_string())
+        match found.or_else(search_path) {
+            Some(python) => Ok(Self { python }),
+            None => Err(RunnerError::InterpreterNotFound { searched }),
+        }
     }
 
-    pub fn run_args(&self, script: impl AsRef<Path>, iface_name: &str) -> Option<Child> {
+    fn command(&self) -> Command {
+        #[allow(unused_mut)]
         let mut cmd = Command::new(&self.python);
 
-        cmd.arg(script.as_ref())
-            .arg("--iface")
-            .arg(iface_name)
-            .creation_flags(0x08000000)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+        #[cfg(windows)]
+        cmd.creation_flags(CREATE_NO_WINDOW);
+
+        cmd
+    }
+
+    pub fn run(&self, script: impl AsRef<Path>) -> Result<String, RunnerError> {
+        let script = script.as_ref();
+
+        let output = self
+            .command()
+            .arg(script)
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|source| RunnerError::Spawn {
+                python: self.python.clone(),
+                source,
+            })?;
+
+        if !output.status.success() {
+            return Err(RunnerError::ScriptFailed {
+                script: script.to_path_buf(),
+                code: output.status.code(),
+                stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            });
+        }
 
-        cmd.spawn().ok()
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 }
@@ -0,0 +1,110 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// sklearn `StandardScaler` parameters exported as JSON by `export_models.py`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StandardScaler {
+    mean: Vec<f64>,
+    scale: Vec<f64>,
+    #[serde(default)]
+    feature_names: Vec<String>,
+}
+
+impl StandardScaler {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+
+        let scaler: Self = serde_json::from_str(&raw)
+            .map_err(|e| format!("Invalid scaler {}: {e}", path.display()))?;
+
+        if scaler.mean.len() != scaler.scale.len() {
+            return Err(format!(
+                "Scaler {} has {} means but {} scales",
+                path.display(),
+                scaler.mean.len(),
+                scaler.scale.len()
+            ));
+        }
+
+        Ok(scaler)
+    }
+
+    /// Checks that the scaler was fitted on exactly `names`, in order.
+    /// Scalers fitted on bare arrays carry no names and only get a length check.
+    pub fn expect_features(&self, names: &[&str]) -> Result<(), String> {
+        if self.mean.len() != names.len() {
+            return Err(format!(
+                "Scaler expects {} features, pipeline produces {}",
+                self.mean.len(),
+                names.len()
+            ));
+        }
+
+        if let Some(i) = self
+            .feature_names
+            .iter()
+            .zip(names)
+            .position(|(have, want)| have != want)
+        {
+            return Err(format!(
+                "Scaler feature {i} is '{}', expected '{}'",
+                self.feature_names[i], names[i]
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn transform(&self, values: &[f64]) -> Vec<f64> {
+        values
+            .iter()
+            .zip(self.mean.iter().zip(&self.scale))
+            .map(|(v, (mean, scale))| {
+                // sklearn stores 1.0 for zero-variance columns; guard hand-written files too
+                let scale = if *scale == 0.0 { 1.0 } else { *scale };
+                (v - mean) / scale
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::flows::{LSTM_FEATURES, LSTM_FEATURE_COUNT};
+
+    #[test]
+    fn bundled_lstm_scaler_matches_the_feature_vector() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("models/LSTM/lstm_scaler.json");
+        let scaler = StandardScaler::load(&path).unwrap();
+
+        assert_eq!(scaler.mean.len(), LSTM_FEATURE_COUNT);
+        scaler.expect_features(&LSTM_FEATURES).unwrap();
+    }
+
+    #[test]
+    fn width_and_name_mismatches_are_rejected() {
+        let scaler = StandardScaler {
+            mean: vec![0.0, 0.0],
+            scale: vec![1.0, 1.0],
+            feature_names: vec!["a".into(), "c".into()],
+        };
+
+        assert!(scaler.expect_features(&["a"]).is_err());
+        assert!(scaler.expect_features(&["a", "b"]).is_err());
+        assert!(scaler.expect_features(&["a", "c"]).is_ok());
+    }
+
+    #[test]
+    fn zero_scale_leaves_values_centered() {
+        let scaler = StandardScaler {
+            mean: vec![1.0, 2.0],
+            scale: vec![2.0, 0.0],
+            feature_names: vec![],
+        };
+
+        assert_eq!(scaler.transform(&[5.0, 7.0]), vec![2.0, 5.0]);
+    }
+}
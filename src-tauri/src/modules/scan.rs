@@ -0,0 +1,410 @@
+use crate::modules::config::ScanConfig;
+use crate::modules::packets::{PROTO_TCP, PROTO_UDP};
+use crate::modules::protocol::{FlowTuple, ModelSource, Verdict};
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+// Bounds memory under a flood of spoofed sources
+const MAX_SOURCES: usize = 4096;
+const MAX_PROBES_PER_SOURCE: usize = 4096;
+
+// Stands in for the target of an alert that spans many hosts
+const MANY_HOSTS: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ScanKind {
+    // Many ports on one host
+    Vertical,
+    // One port on many hosts
+    Horizontal,
+    // Many handshakes the source never completed
+    Syn,
+}
+
+impl ScanKind {
+    fn label(self) -> &'static str {
+        match self {
+            ScanKind::Vertical => "PORT_SCAN",
+            ScanKind::Horizontal => "HOST_SWEEP",
+            ScanKind::Syn => "SYN_SCAN",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Probe {
+    at: DateTime<Utc>,
+    dst_ip: String,
+    dst_port: u16,
+    half_open: bool,
+    // Half-open, or no reply at all
+    unanswered: bool,
+}
+
+#[derive(Debug, Default)]
+struct Source {
+    probes: VecDeque<Probe>,
+    // (kind, target) → when it was last alerted, to alert once per window
+    alerted: HashMap<(ScanKind, String), DateTime<Utc>>,
+}
+
+impl Source {
+    fn last_seen(&self) -> Option<DateTime<Utc>> {
+        self.probes.back().map(|p| p.at)
+    }
+}
+
+/// Cross-flow detector: per source address, keeps the flows of the last
+/// `window_secs` and alerts when they reach too many distinct ports or hosts.
+#[derive(Debug, Default)]
+pub struct ScanDetector {
+    sources: Mutex<HashMap<String, Source>>,
+}
+
+impl ScanDetector {
+    /// Feeds one classified flow. Returns an anomalous verdict per scan that
+    /// crossed its threshold, with the distinct target count as score.
+    pub fn observe(&self, verdict: &Verdict, config: &ScanConfig) -> Vec<Verdict> {
+        let flow = &verdict.flow;
+        if !config.enabled || !matches!(flow.protocol, PROTO_TCP | PROTO_UDP) {
+            return Vec::new();
+        }
+
+        let now = verdict.timestamp;
+        let window = Duration::seconds(config.window_secs as i64);
+        let mut sources = self.sources.lock().unwrap();
+
+        if !sources.contains_key(&flow.src_ip) {
+            evict(&mut sources, now - window);
+        }
+        let source = sources.entry(flow.src_ip.clone()).or_default();
+
+        // ---- Slide the window ----
+        let half_open = verdict.fwd_flags.is_some_and(|f| f.is_half_open());
+        source.probes.push_back(Probe {
+            at: now,
+            dst_ip: flow.dst_ip.clone(),
+            dst_port: flow.dst_port,
+            half_open,
+            unanswered: half_open || verdict.bwd_packets == Some(0),
+        });
+        while source.probes.len() > MAX_PROBES_PER_SOURCE
+            || source.probes.front().is_some_and(|p| p.at < now - window)
+        {
+            source.probes.pop_front();
+        }
+        source.alerted.retain(|_, at| *at >= now - window);
+
+        let probe = source.probes.back().unwrap();
+        let mut found = Vec::new();
+
+        // ---- Vertical: distinct ports on this host ----
+        let ports: HashSet<u16> = source
+            .probes
+            .iter()
+            .filter(|p| p.dst_ip == probe.dst_ip)
+            .map(|p| p.dst_port)
+            .collect();
+        if ports.len() >= config.port_threshold {
+            found.push((ScanKind::Vertical, probe.dst_ip.clone(), 0, ports.len()));
+        }
+
+        // ---- Horizontal: distinct hosts on this port that didn't answer ----
+        // Answered connections to one port are ordinary clients (HTTPS, DNS)
+        if probe.unanswered {
+            let hosts: HashSet<&str> = source
+                .probes
+                .iter()
+                .filter(|p| p.unanswered && p.dst_port == probe.dst_port)
+                .map(|p| p.dst_ip.as_str())
+                .collect();
+            if hosts.len() >= config.host_threshold {
+                found.push((
+                    ScanKind::Horizontal,
+                    MANY_HOSTS.into(),
+                    probe.dst_port,
+                    hosts.len(),
+                ));
+            }
+        }
+
+        // ---- SYN: distinct half-open targets ----
+        if probe.half_open {
+            let targets: HashSet<(&str, u16)> = source
+                .probes
+                .iter()
+                .filter(|p| p.half_open)
+                .map(|p| (p.dst_ip.as_str(), p.dst_port))
+                .collect();
+            if targets.len() >= config.syn_threshold {
+                let hosts: HashSet<&str> = targets.iter().map(|(ip, _)| *ip).collect();
+                let target = match hosts.len() {
+                    1 => probe.dst_ip.clone(),
+                    _ => MANY_HOSTS.into(),
+                };
+                found.push((ScanKind::Syn, target, 0, targets.len()));
+            }
+        }
+
+        let first_seen = source.probes.front().map(|p| p.at);
+        let mut alerts = Vec::new();
+
+        for (kind, target, dst_port, count) in found {
+            let key = (kind, format!("{target}:{dst_port}"));
+            if source.alerted.contains_key(&key) {
+                continue;
+            }
+            source.alerted.insert(key, now);
+
+            alerts.push(Verdict {
+                iface: verdict.iface.clone(),
+                flow: FlowTuple {
+                    src_ip: flow.src_ip.clone(),
+                    src_port: 0,
+                    dst_ip: target,
+                    dst_port,
+                    protocol: flow.protocol,
+                },
+                label: kind.label().into(),
+                is_anomaly: true,
+                score: count as f64,
+                model: Some(ModelSource::Scan),
+                flow_start: first_seen,
+                fwd_flags: None,
+                bytes: None,
+                bwd_packets: None,
+                period_secs: None,
+                domains: Vec::new(),
+                macs: Vec::new(),
+                tls: None,
+                timestamp: now,
+            });
+        }
+
+        alerts
+    }
+}
+
+/// Makes room for a new source: drops idle ones, then the least recent.
+fn evict(sources: &mut HashMap<String, Source>, cutoff: DateTime<Utc>) {
+    if sources.len() < MAX_SOURCES {
+        return;
+    }
+
+    sources.retain(|_, source| source.last_seen().is_some_and(|at| at >= cutoff));
+
+    if sources.len() >= MAX_SOURCES {
+        let oldest = sources
+            .iter()
+            .min_by_key(|(_, source)| source.last_seen())
+            .map(|(ip, _)| ip.clone());
+        if let Some(ip) = oldest {
+            sources.remove(&ip);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::protocol::TcpFlags;
+
+    const ANSWERED: Option<TcpFlags> = Some(TcpFlags {
+        syn: 1,
+        ack: 2,
+        rst: 0,
+        fin: 1,
+    });
+    const HALF_OPEN: Option<TcpFlags> = Some(TcpFlags {
+        syn: 1,
+        ack: 0,
+        rst: 0,
+        fin: 0,
+    });
+
+    fn config() -> ScanConfig {
+        ScanConfig {
+            port_threshold: 5,
+            host_threshold: 4,
+            syn_threshold: 3,
+            ..ScanConfig::default()
+        }
+    }
+
+    fn probe(
+        src: &str,
+        dst: &str,
+        port: u16,
+        secs: i64,
+        flags: Option<TcpFlags>,
+        bwd: u64,
+    ) -> Verdict {
+        Verdict {
+            iface: "eth0".into(),
+            flow: FlowTuple {
+                src_ip: src.into(),
+                src_port: 40000,
+                dst_ip: dst.into(),
+                dst_port: port,
+                protocol: PROTO_TCP,
+            },
+            label: "BENIGN".into(),
+            is_anomaly: false,
+            score: 0.1,
+            model: Some(ModelSource::Xgb),
+            flow_start: None,
+            fwd_flags: flags,
+            bytes: Some(120),
+            bwd_packets: Some(bwd),
+            period_secs: None,
+            domains: Vec::new(),
+            macs: Vec::new(),
+            tls: None,
+            timestamp: DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap(),
+        }
+    }
+
+    fn labels(alerts: &[Verdict]) -> Vec<&str> {
+        alerts.iter().map(|v| v.label.as_str()).collect()
+    }
+
+    #[test]
+    fn vertical_scan_alerts_at_the_port_threshold() {
+        let detector = ScanDetector::default();
+        let config = config();
+
+        for port in 1..5 {
+            let alerts = detector.observe(
+                &probe("10.0.0.5", "10.0.0.9", port, 0, ANSWERED, 3),
+                &config,
+            );
+            assert!(alerts.is_empty());
+        }
+        let alerts = detector.observe(&probe("10.0.0.5", "10.0.0.9", 5, 0, ANSWERED, 3), &config);
+        assert_eq!(labels(&alerts), ["PORT_SCAN"]);
+        assert_eq!(alerts[0].flow.dst_ip, "10.0.0.9");
+        assert_eq!(alerts[0].score, 5.0);
+    }
+
+    #[test]
+    fn horizontal_sweep_counts_only_unanswered_probes() {
+        let detector = ScanDetector::default();
+        let config = config();
+
+        // A browser talking to many HTTPS servers is not a sweep
+        for host in 1..=10 {
+            let dst = format!("203.0.113.{host}");
+            assert!(detector
+                .observe(&probe("10.0.0.5", &dst, 443, 0, ANSWERED, 5), &config)
+                .is_empty());
+        }
+
+        // Silent UDP-style probes and refused handshakes both count
+        for host in 1..=3 {
+            let dst = format!("198.51.100.{host}");
+            let flags = if host == 1 { HALF_OPEN } else { None };
+            assert!(detector
+                .observe(&probe("10.0.0.5", &dst, 443, 1, flags, 0), &config)
+                .is_empty());
+        }
+        let alerts = detector.observe(&probe("10.0.0.5", "198.51.100.4", 443, 1, None, 0), &config);
+        assert_eq!(labels(&alerts), ["HOST_SWEEP"]);
+        assert_eq!(alerts[0].flow.dst_ip, MANY_HOSTS);
+        assert_eq!(alerts[0].flow.dst_port, 443);
+        assert_eq!(alerts[0].score, 4.0);
+    }
+
+    #[test]
+    fn syn_scan_alerts_on_distinct_half_open_targets() {
+        let detector = ScanDetector::default();
+        let config = config();
+
+        // Completed handshakes and repeats of one target don't count
+        detector.observe(&probe("10.0.0.5", "10.0.0.9", 22, 0, ANSWERED, 4), &config);
+        detector.observe(&probe("10.0.0.5", "10.0.0.9", 80, 0, HALF_OPEN, 1), &config);
+        assert!(detector
+            .observe(&probe("10.0.0.5", "10.0.0.9", 80, 1, HALF_OPEN, 1), &config)
+            .is_empty());
+        assert!(detector
+            .observe(&probe("10.0.0.5", "10.0.0.9", 81, 1, HALF_OPEN, 1), &config)
+            .is_empty());
+
+        let alerts = detector.observe(&probe("10.0.0.5", "10.0.0.9", 82, 2, HALF_OPEN, 1), &config);
+        assert_eq!(labels(&alerts), ["SYN_SCAN"]);
+        // One host is named, many are not
+        assert_eq!(alerts[0].flow.dst_ip, "10.0.0.9");
+        assert_eq!(alerts[0].score, 3.0);
+
+        let alerts = detector.observe(&probe("10.0.0.6", "10.0.0.1", 80, 0, HALF_OPEN, 1), &config);
+        assert!(alerts.is_empty());
+        detector.observe(&probe("10.0.0.6", "10.0.0.2", 80, 0, HALF_OPEN, 1), &config);
+        let alerts = detector.observe(&probe("10.0.0.6", "10.0.0.3", 81, 0, HALF_OPEN, 1), &config);
+        assert_eq!(labels(&alerts), ["SYN_SCAN"]);
+        assert_eq!(alerts[0].flow.dst_ip, MANY_HOSTS);
+    }
+
+    #[test]
+    fn each_scan_alerts_once_per_window() {
+        let detector = ScanDetector::default();
+        let config = config();
+
+        let mut alerts = Vec::new();
+        for port in 1..=20 {
+            alerts.extend(detector.observe(
+                &probe("10.0.0.5", "10.0.0.9", port, 1, ANSWERED, 2),
+                &config,
+            ));
+        }
+        assert_eq!(labels(&alerts), ["PORT_SCAN"]);
+
+        // Once the window has slid past the first alert, the scan is reported again
+        let later = config.window_secs as i64 + 2;
+        let mut alerts = Vec::new();
+        for port in 21..=25 {
+            alerts.extend(detector.observe(
+                &probe("10.0.0.5", "10.0.0.9", port, later, ANSWERED, 2),
+                &config,
+            ));
+        }
+        assert_eq!(labels(&alerts), ["PORT_SCAN"]);
+    }
+
+    #[test]
+    fn source_table_is_bounded() {
+        let detector = ScanDetector::default();
+        let config = config();
+        let source = |n: usize| format!("10.{}.{}.{}", n >> 16, (n >> 8) & 0xff, n & 0xff);
+
+        for n in 0..MAX_SOURCES {
+            detector.observe(
+                &probe(&source(n), "10.255.0.1", 80, n as i64 / 1000, ANSWERED, 1),
+                &config,
+            );
+        }
+
+        // All active: the least recently seen goes
+        detector.observe(
+            &probe(&source(MAX_SOURCES), "10.255.0.1", 80, 5, ANSWERED, 1),
+            &config,
+        );
+        {
+            let sources = detector.sources.lock().unwrap();
+            assert_eq!(sources.len(), MAX_SOURCES);
+            assert!(sources.contains_key(&source(MAX_SOURCES)));
+            let oldest = (0..MAX_SOURCES)
+                .filter(|n| !sources.contains_key(&source(*n)))
+                .count();
+            assert_eq!(oldest, 1);
+        }
+
+        // All idle past the window: they all go at once
+        let later = config.window_secs as i64 + 10;
+        detector.observe(
+            &probe("192.168.0.1", "10.255.0.1", 80, later, ANSWERED, 1),
+            &config,
+        );
+        assert_eq!(detector.sources.lock().unwrap().len(), 1);
+    }
+}
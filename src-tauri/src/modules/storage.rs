@@ -0,0 +1,706 @@
+use crate::modules::alerts::Alert;
+use crate::modules::protocol::{ModelSource, Verdict};
+use crate::modules::tls::TlsInfo;
+use crate::{DefaultStruct, NetworkInfo};
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, Row};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub const DB_FILE: &str = "sentinelai.db";
+pub const DEFAULT_RETENTION_DAYS: u32 = 30;
+pub const DEFAULT_QUERY_LIMIT: usize = 500;
+
+const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+// ---------------------------------------------------------------------
+// SCHEMA MIGRATIONS
+//
+// Applied in order, PRAGMA user_version records how many have run.
+// Never edit a shipped migration, append a new one.
+// Timestamps are unix milliseconds (UTC) so range scans use the indexes.
+// ---------------------------------------------------------------------
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE alerts (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        iface       TEXT    NOT NULL,
+        src_ip      TEXT    NOT NULL,
+        src_port    INTEGER NOT NULL,
+        dst_ip      TEXT    NOT NULL,
+        dst_port    INTEGER NOT NULL,
+        protocol    INTEGER NOT NULL,
+        label       TEXT    NOT NULL,
+        model       TEXT,
+        score       REAL    NOT NULL,
+        flow_start  INTEGER,
+        detected_at INTEGER NOT NULL,
+        received_at INTEGER NOT NULL
+    );
+    CREATE INDEX idx_alerts_time   ON alerts (detected_at);
+    CREATE INDEX idx_alerts_iface  ON alerts (iface, detected_at);
+    CREATE INDEX idx_alerts_label  ON alerts (label, detected_at);
+    CREATE INDEX idx_alerts_src_ip ON alerts (src_ip, detected_at);
+    CREATE INDEX idx_alerts_dst_ip ON alerts (dst_ip, detected_at);
+
+    CREATE TABLE flows (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        iface       TEXT    NOT NULL,
+        src_ip      TEXT    NOT NULL,
+        src_port    INTEGER NOT NULL,
+        dst_ip      TEXT    NOT NULL,
+        dst_port    INTEGER NOT NULL,
+        protocol    INTEGER NOT NULL,
+        label       TEXT    NOT NULL,
+        is_anomaly  INTEGER NOT NULL,
+        model       TEXT,
+        score       REAL    NOT NULL,
+        flow_start  INTEGER,
+        flow_end    INTEGER NOT NULL
+    );
+    CREATE INDEX idx_flows_time   ON flows (flow_end);
+    CREATE INDEX idx_flows_iface  ON flows (iface, flow_end);
+    CREATE INDEX idx_flows_label  ON flows (label, flow_end);
+    CREATE INDEX idx_flows_src_ip ON flows (src_ip, flow_end);
+    CREATE INDEX idx_flows_dst_ip ON flows (dst_ip, flow_end);
+
+    CREATE TABLE snapshots (
+        id                INTEGER PRIMARY KEY AUTOINCREMENT,
+        taken_at          INTEGER NOT NULL,
+        iface             TEXT    NOT NULL,
+        name              TEXT    NOT NULL,
+        status            TEXT    NOT NULL,
+        ipv4              TEXT,
+        ipv6              TEXT,
+        received_bytes    INTEGER NOT NULL,
+        transmitted_bytes INTEGER NOT NULL,
+        download          REAL    NOT NULL,
+        upload            REAL    NOT NULL,
+        is_anomalous      INTEGER NOT NULL,
+        anomaly_type      TEXT
+    );
+    CREATE INDEX idx_snapshots_time  ON snapshots (taken_at);
+    CREATE INDEX idx_snapshots_iface ON snapshots (name, taken_at);",
+    "ALTER TABLE alerts ADD COLUMN pid INTEGER;
+    ALTER TABLE alerts ADD COLUMN process TEXT;
+    CREATE INDEX idx_alerts_process ON alerts (process, detected_at);",
+    "ALTER TABLE alerts ADD COLUMN period_secs REAL;",
+    // JSON array, NULL when empty
+    "ALTER TABLE alerts ADD COLUMN domains TEXT;",
+    // TLS metadata as JSON, NULL for flows without a handshake
+    "ALTER TABLE alerts ADD COLUMN tls TEXT;
+    ALTER TABLE flows ADD COLUMN tls TEXT;",
+    // JSON array, NULL when empty
+    "ALTER TABLE alerts ADD COLUMN macs TEXT;",
+];
+
+#[derive(Debug)]
+pub enum StoreError {
+    Open { path: PathBuf, source: rusqlite::Error },
+    Migration { version: usize, source: rusqlite::Error },
+    Sql(rusqlite::Error),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Open { path, source } => {
+                write!(f, "Failed to open {}: {source}", path.display())
+            }
+            StoreError::Migration { version, source } => {
+                write!(f, "Schema migration {version} failed: {source}")
+            }
+            StoreError::Sql(e) => write!(f, "Storage error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StoreError::Open { source, .. } | StoreError::Migration { source, .. } => Some(source),
+            StoreError::Sql(e) => Some(e),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for StoreError {
+    fn from(e: rusqlite::Error) -> Self {
+        StoreError::Sql(e)
+    }
+}
+
+/// Filters shared by every history query. All fields are optional;
+/// `ip` matches either end of a flow.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub iface: Option<String>,
+    pub label: Option<String>,
+    pub ip: Option<String>,
+    pub limit: Option<usize>,
+}
+
+DefaultStruct! {
+    pub struct FlowRecord {
+        id: i64,
+        iface: String,
+        src_ip: String,
+        src_port: u16,
+        dst_ip: String,
+        dst_port: u16,
+        protocol: u8,
+        label: String,
+        is_anomaly: bool,
+        model: Option<ModelSource>,
+        score: f64,
+        flow_start: Option<DateTime<Utc>>,
+        flow_end: DateTime<Utc>,
+        tls: Option<TlsInfo>,
+    }
+}
+
+DefaultStruct! {
+    pub struct InterfaceSnapshot {
+        taken_at: DateTime<Utc>,
+        iface: String,
+        name: String,
+        status: String,
+        ipv4: Option<String>,
+        ipv6: Option<String>,
+        received_bytes: u64,
+        transmitted_bytes: u64,
+        download: f64,
+        upload: f64,
+        is_anomalous: bool,
+        anomaly_type: Option<String>,
+    }
+}
+
+fn to_ms(ts: DateTime<Utc>) -> i64 {
+    ts.timestamp_millis()
+}
+
+fn from_ms(ms: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(ms).unwrap_or_default()
+}
+
+fn model_name(model: Option<ModelSource>) -> Option<&'static str> {
+    model.map(|m| match m {
+        ModelSource::Xgb => "XGB",
+        ModelSource::Lstm => "LSTM",
+        ModelSource::Scan => "SCAN",
+        ModelSource::Beacon => "BEACON",
+        ModelSource::Dns => "DNS",
+        ModelSource::Tls => "TLS",
+        ModelSource::Arp => "ARP",
+    })
+}
+
+fn parse_model(name: Option<String>) -> Option<ModelSource> {
+    match name.as_deref() {
+        Some("XGB") => Some(ModelSource::Xgb),
+        Some("LSTM") => Some(ModelSource::Lstm),
+        Some("SCAN") => Some(ModelSource::Scan),
+        Some("BEACON") => Some(ModelSource::Beacon),
+        Some("DNS") => Some(ModelSource::Dns),
+        Some("TLS") => Some(ModelSource::Tls),
+        Some("ARP") => Some(ModelSource::Arp),
+        _ => None,
+    }
+}
+
+/// Builds `WHERE ... ORDER BY ... LIMIT` for one table. Label and IP filters
+/// only apply to flow tables (`flow_cols`).
+fn filter_clause(
+    query: &HistoryQuery,
+    time_col: &str,
+    iface_col: &str,
+    flow_cols: bool,
+) -> (String, Vec<SqlValue>) {
+    let mut clauses = Vec::new();
+    let mut args = Vec::new();
+
+    if let Some(from) = query.from {
+        clauses.push(format!("{time_col} >= ?"));
+        args.push(SqlValue::Integer(to_ms(from)));
+    }
+    if let Some(to) = query.to {
+        clauses.push(format!("{time_col} <= ?"));
+        args.push(SqlValue::Integer(to_ms(to)));
+    }
+    if let Some(iface) = &query.iface {
+        clauses.push(format!("{iface_col} = ?"));
+        args.push(SqlValue::Text(iface.clone()));
+    }
+    if let (true, Some(label)) = (flow_cols, &query.label) {
+        clauses.push("label = ?".to_string());
+        args.push(SqlValue::Text(label.to_uppercase()));
+    }
+    if let (true, Some(ip)) = (flow_cols, &query.ip) {
+        clauses.push("(src_ip = ? OR dst_ip = ?)".to_string());
+        args.push(SqlValue::Text(ip.clone()));
+        args.push(SqlValue::Text(ip.clone()));
+    }
+
+    let mut sql = String::new();
+    if !clauses.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&clauses.join(" AND "));
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    sql.push_str(&format!(" ORDER BY {time_col} DESC LIMIT {limit}"));
+
+    (sql, args)
+}
+
+fn list_from_row(row: &Row, column: &str) -> rusqlite::Result<Vec<String>> {
+    Ok(row
+        .get::<_, Option<String>>(column)?
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+fn list_json(list: &[String]) -> Option<String> {
+    (!list.is_empty())
+        .then(|| serde_json::to_string(list).ok())
+        .flatten()
+}
+
+fn tls_from_row(row: &Row) -> rusqlite::Result<Option<TlsInfo>> {
+    Ok(row
+        .get::<_, Option<String>>("tls")?
+        .and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+fn tls_json(tls: Option<&TlsInfo>) -> Option<String> {
+    tls.and_then(|tls| serde_json::to_string(tls).ok())
+}
+
+fn alert_from_row(row: &Row) -> rusqlite::Result<Alert> {
+    Ok(Alert {
+        id: row.get::<_, i64>("id")? as u64,
+        iface: row.get("iface")?,
+        src_ip: row.get("src_ip")?,
+        src_port: row.get("src_port")?,
+        dst_ip: row.get("dst_ip")?,
+        dst_port: row.get("dst_port")?,
+        protocol: row.get("protocol")?,
+        label: row.get("label")?,
+        model: parse_model(row.get("model")?),
+        score: row.get("score")?,
+        flow_start: row.get::<_, Option<i64>>("flow_start")?.map(from_ms),
+        detected_at: from_ms(row.get("detected_at")?),
+        received_at: from_ms(row.get("received_at")?),
+        pid: row.get("pid")?,
+        process: row.get("process")?,
+        period_secs: row.get("period_secs")?,
+        domains: list_from_row(row, "domains")?,
+        macs: list_from_row(row, "macs")?,
+        tls: tls_from_row(row)?,
+    })
+}
+
+fn flow_from_row(row: &Row) -> rusqlite::Result<FlowRecord> {
+    Ok(FlowRecord {
+        id: row.get("id")?,
+        iface: row.get("iface")?,
+        src_ip: row.get("src_ip")?,
+        src_port: row.get("src_port")?,
+        dst_ip: row.get("dst_ip")?,
+        dst_port: row.get("dst_port")?,
+        protocol: row.get("protocol")?,
+        label: row.get("label")?,
+        is_anomaly: row.get("is_anomaly")?,
+        model: parse_model(row.get("model")?),
+        score: row.get("score")?,
+        flow_start: row.get::<_, Option<i64>>("flow_start")?.map(from_ms),
+        flow_end: from_ms(row.get("flow_end")?),
+        tls: tls_from_row(row)?,
+    })
+}
+
+fn snapshot_from_row(row: &Row) -> rusqlite::Result<InterfaceSnapshot> {
+    Ok(InterfaceSnapshot {
+        taken_at: from_ms(row.get("taken_at")?),
+        iface: row.get("iface")?,
+        name: row.get("name")?,
+        status: row.get("status")?,
+        ipv4: row.get("ipv4")?,
+        ipv6: row.get("ipv6")?,
+        received_bytes: row.get::<_, i64>("received_bytes")? as u64,
+        transmitted_bytes: row.get::<_, i64>("transmitted_bytes")? as u64,
+        download: row.get("download")?,
+        upload: row.get("upload")?,
+        is_anomalous: row.get("is_anomalous")?,
+        anomaly_type: row.get("anomaly_type")?,
+    })
+}
+
+/// Local SQLite history of alerts, classified flows and interface snapshots.
+pub struct Store {
+    conn: Mutex<Connection>,
+    retention_days: AtomicU32,
+}
+
+impl fmt::Debug for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Store")
+            .field("retention_days", &self.retention_days())
+            .finish()
+    }
+}
+
+impl Store {
+    /// Opens (or creates) the database and brings its schema up to date.
+    /// A `retention_days` of 0 keeps history forever.
+    pub fn open(path: &Path, retention_days: u32) -> Result<Self, StoreError> {
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+
+        let conn = Connection::open(path).map_err(|source| StoreError::Open {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        // WAL keeps readers (history queries) from blocking the worker threads
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+
+        Self::with_connection(conn, retention_days)
+    }
+
+    fn with_connection(conn: Connection, retention_days: u32) -> Result<Self, StoreError> {
+        let mut store = Self {
+            conn: Mutex::new(conn),
+            retention_days: AtomicU32::new(retention_days),
+        };
+        store.migrate()?;
+
+        Ok(store)
+    }
+
+    pub fn retention_days(&self) -> u32 {
+        self.retention_days.load(Ordering::Relaxed)
+    }
+
+    /// Takes effect on the next prune.
+    pub fn set_retention_days(&self, days: u32) {
+        self.retention_days.store(days, Ordering::Relaxed);
+    }
+
+    fn migrate(&mut self) -> Result<(), StoreError> {
+        let conn = self.conn.get_mut().unwrap();
+        let applied: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        for (index, sql) in MIGRATIONS.iter().enumerate().skip(applied) {
+            let version = index + 1;
+            let migration = |e| StoreError::Migration { version, source: e };
+
+            let tx = conn.transaction().map_err(migration)?;
+            tx.execute_batch(sql).map_err(migration)?;
+            tx.pragma_update(None, "user_version", version).map_err(migration)?;
+            tx.commit().map_err(migration)?;
+        }
+
+        Ok(())
+    }
+
+    // ---- Writes ----
+
+    pub fn insert_alert(&self, alert: &Alert) -> Result<i64, StoreError> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO alerts (iface, src_ip, src_port, dst_ip, dst_port, protocol, label,
+                                 model, score, flow_start, detected_at, received_at,
+                                 pid, process, period_secs, domains, tls, macs)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                     ?17, ?18)",
+            params![
+                alert.iface,
+                alert.src_ip,
+                alert.src_port,
+                alert.dst_ip,
+                alert.dst_port,
+                alert.protocol,
+                alert.label,
+                model_name(alert.model),
+                alert.score,
+                alert.flow_start.map(to_ms),
+                to_ms(alert.detected_at),
+                to_ms(alert.received_at),
+                alert.pid,
+                alert.process,
+                alert.period_secs,
+                list_json(&alert.domains),
+                tls_json(alert.tls.as_ref()),
+                list_json(&alert.macs),
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn insert_flows(&self, verdicts: &[Verdict]) -> Result<(), StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO flows (iface, src_ip, src_port, dst_ip, dst_port, protocol, label,
+                                    is_anomaly, model, score, flow_start, flow_end, tls)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+
+            for verdict in verdicts {
+                stmt.execute(params![
+                    verdict.iface,
+                    verdict.flow.src_ip,
+                    verdict.flow.src_port,
+                    verdict.flow.dst_ip,
+                    verdict.flow.dst_port,
+                    verdict.flow.protocol,
+                    verdict.label.to_uppercase(),
+                    verdict.is_anomaly,
+                    model_name(verdict.model),
+                    verdict.score,
+                    verdict.flow_start.map(to_ms),
+                    to_ms(verdict.timestamp),
+                    tls_json(verdict.tls.as_deref()),
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    pub fn insert_snapshots(&self, networks: &[NetworkInfo]) -> Result<(), StoreError> {
+        let mut conn = self.conn.lock().unwrap();
+        let taken_at = to_ms(Utc::now());
+
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO snapshots (taken_at, iface, name, status, ipv4, ipv6,
+                                        received_bytes, transmitted_bytes, download, upload,
+                                        is_anomalous, anomaly_type)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            )?;
+
+            for net in networks {
+                stmt.execute(params![
+                    taken_at,
+                    net.id,
+                    net.name,
+                    net.status,
+                    net.ip_info.ipv4,
+                    net.ip_info.ipv6,
+                    net.received_bytes as i64,
+                    net.transmitted_bytes as i64,
+                    net.bandwidth.download,
+                    net.bandwidth.upload,
+                    net.anomaly.is_anomalous,
+                    net.anomaly.anomaly_type,
+                ])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+
+    // ---- Queries ----
+
+    pub fn query_alerts(&self, query: &HistoryQuery) -> Result<Vec<Alert>, StoreError> {
+        let (filter, args) = filter_clause(query, "detected_at", "iface", true);
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!("SELECT * FROM alerts{filter}"))?;
+        let rows = stmt.query_map(params_from_iter(args), alert_from_row)?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn query_flows(&self, query: &HistoryQuery) -> Result<Vec<FlowRecord>, StoreError> {
+        let (filter, args) = filter_clause(query, "flow_end", "iface", true);
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!("SELECT * FROM flows{filter}"))?;
+        let rows = stmt.query_map(params_from_iter(args), flow_from_row)?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Snapshots are keyed by interface name, `query.iface` matches `name`.
+    pub fn query_snapshots(&self, query: &HistoryQuery) -> Result<Vec<InterfaceSnapshot>, StoreError> {
+        let (filter, args) = filter_clause(query, "taken_at", "name", false);
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT * FROM snapshots{filter}"))?;
+        let rows = stmt.query_map(params_from_iter(args), snapshot_from_row)?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    // ---- Retention ----
+
+    /// Deletes rows older than the retention window. Returns how many went.
+    pub fn prune(&self) -> Result<usize, StoreError> {
+        let retention_days = self.retention_days();
+        if retention_days == 0 {
+            return Ok(0);
+        }
+
+        let cutoff = to_ms(Utc::now() - Duration::days(retention_days as i64));
+        let conn = self.conn.lock().unwrap();
+
+        let mut removed = 0;
+        removed += conn.execute("DELETE FROM alerts WHERE detected_at < ?1", [cutoff])?;
+        removed += conn.execute("DELETE FROM flows WHERE flow_end < ?1", [cutoff])?;
+        removed += conn.execute("DELETE FROM snapshots WHERE taken_at < ?1", [cutoff])?;
+
+        Ok(removed)
+    }
+}
+
+/// Retention from `SENTINELAI_RETENTION_DAYS`, when set. Overrides the
+/// config file.
+pub fn retention_from_env() -> Option<u32> {
+    std::env::var("SENTINELAI_RETENTION_DAYS").ok().and_then(|v| {
+        v.trim()
+            .parse()
+            .inspect_err(|e| eprintln!("SENTINELAI_RETENTION_DAYS: {e}"))
+            .ok()
+    })
+}
+
+/// Prunes once at startup, then hourly.
+pub fn start_retention(store: Arc<Store>) {
+    thread::spawn(move || loop {
+        match store.prune() {
+            Ok(0) => {}
+            Ok(n) => eprintln!("storage: pruned {n} rows older than {} days", store.retention_days()),
+            Err(e) => eprintln!("storage: {e}"),
+        }
+
+        thread::sleep(PRUNE_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(detected_at: DateTime<Utc>) -> Alert {
+        Alert {
+            id: 0,
+            iface: "eth0".to_string(),
+            src_ip: "192.168.1.10".to_string(),
+            src_port: 51000,
+            dst_ip: "203.0.113.7".to_string(),
+            dst_port: 443,
+            protocol: 6,
+            label: "BOT".to_string(),
+            model: Some(ModelSource::Xgb),
+            score: 0.9,
+            flow_start: None,
+            detected_at,
+            received_at: detected_at,
+            pid: Some(42),
+            process: Some("curl".to_string()),
+            period_secs: None,
+            domains: vec!["example.com".to_string()],
+            macs: Vec::new(),
+            tls: None,
+        }
+    }
+
+    fn version(store: &Store) -> usize {
+        let conn = store.conn.lock().unwrap();
+        conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn new_database_gets_every_migration() {
+        let store = Store::with_connection(Connection::open_in_memory().unwrap(), 30).unwrap();
+        assert_eq!(version(&store), MIGRATIONS.len());
+
+        store.insert_alert(&alert(Utc::now())).unwrap();
+        let alerts = store.query_alerts(&HistoryQuery::default()).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].process.as_deref(), Some("curl"));
+        assert_eq!(alerts[0].domains, ["example.com"]);
+    }
+
+    #[test]
+    fn older_schema_is_upgraded_in_place() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        conn.execute(
+            "INSERT INTO alerts (iface, src_ip, src_port, dst_ip, dst_port, protocol, label,
+                                 score, detected_at, received_at)
+             VALUES ('eth0', '10.0.0.1', 1, '10.0.0.2', 2, 6, 'DDOS', 0.5, 1, 1)",
+            [],
+        )
+        .unwrap();
+
+        let mut store = Store::with_connection(conn, 30).unwrap();
+        assert_eq!(version(&store), MIGRATIONS.len());
+
+        // Already current: nothing runs twice
+        store.migrate().unwrap();
+        assert_eq!(version(&store), MIGRATIONS.len());
+
+        let alerts = store.query_alerts(&HistoryQuery::default()).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].label, "DDOS");
+        assert_eq!(alerts[0].pid, None);
+        assert!(alerts[0].domains.is_empty() && alerts[0].macs.is_empty());
+    }
+
+    #[test]
+    fn prune_drops_only_rows_past_retention() {
+        let store = Store::with_connection(Connection::open_in_memory().unwrap(), 30).unwrap();
+        let now = Utc::now();
+        store.insert_alert(&alert(now - Duration::days(31))).unwrap();
+        store.insert_alert(&alert(now - Duration::days(29))).unwrap();
+        store.insert_alert(&alert(now)).unwrap();
+        {
+            let conn = store.conn.lock().unwrap();
+            for taken_at in [now - Duration::days(40), now] {
+                conn.execute(
+                    "INSERT INTO snapshots (taken_at, iface, name, status, received_bytes,
+                                            transmitted_bytes, download, upload, is_anomalous)
+                     VALUES (?1, 'eth0', 'eth0', 'Active', 0, 0, 0, 0, 0)",
+                    [to_ms(taken_at)],
+                )
+                .unwrap();
+            }
+        }
+
+        assert_eq!(store.prune().unwrap(), 2);
+        assert_eq!(store.query_alerts(&HistoryQuery::default()).unwrap().len(), 2);
+        assert_eq!(store.query_snapshots(&HistoryQuery::default()).unwrap().len(), 1);
+
+        // A shorter window applies from the next prune
+        store.set_retention_days(1);
+        assert_eq!(store.prune().unwrap(), 1);
+
+        store.insert_alert(&alert(now - Duration::days(400))).unwrap();
+        store.set_retention_days(0);
+        assert_eq!(store.prune().unwrap(), 0);
+        assert_eq!(store.query_alerts(&HistoryQuery::default()).unwrap().len(), 2);
+    }
+}
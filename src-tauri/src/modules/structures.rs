@@ -1,23 +1,100 @@
 use chrono::{DateTime, Utc};
 use serde::Serialize;
-use std::collections::HashMap;
-use std::process::Child;
-use std::sync::{Mutex, RwLock};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+use crate::modules::alerts::AlertLog;
+use crate::modules::bandwidth::RateTracker;
+use crate::modules::beacon::BeaconDetector;
+use crate::modules::config::Config;
+use crate::modules::deltas::ChangeTracker;
+use crate::modules::detector::Detector;
+use crate::modules::arp::ArpMonitor;
+use crate::modules::dns::{DnsDetector, DnsLog};
+use crate::modules::tls::TlsBlocklist;
+use crate::modules::monitoring::{MonitoringState, Wakeup};
+use crate::modules::networks::CaptureDevice;
+use crate::modules::offline::Analyses;
+use crate::modules::pipeline::PipelineCounters;
+use crate::modules::scan::ScanDetector;
+use crate::modules::privacy::Privacy;
+use crate::modules::storage::Store;
+use crate::modules::supervisor::Worker;
 
 use crate::{DefaultStruct, GlobalStruct};
 
 GlobalStruct! {
     pub struct AppState {
         networks: RwLock<Vec<NetworkInfo>>,
-        workers: Mutex<HashMap<String, Child>>,
+        workers: Mutex<HashMap<String, Worker>>,
+        // Interface key → capture device name, rebuilt every monitor cycle
         iface_map: Mutex<HashMap<String, String>>,
+        capture_devices: Mutex<Vec<CaptureDevice>>,
+        detector: RwLock<Option<Arc<Detector>>>,
+        alerts: AlertLog,
+        store: RwLock<Option<Arc<Store>>>,
+        rates: RateTracker,
+        privacy: RwLock<Privacy>,
+        // Sockets bound to 0.0.0.0 / ::, not owned by any one interface
+        wildcard_sockets: RwLock<Vec<Connection>>,
+        processes: RwLock<Vec<ProcessInfo>>,
+        // This host's interface addresses, for matching flows to listeners
+        local_addrs: RwLock<HashSet<IpAddr>>,
+        // Last out-of-cycle socket rescan by `handle_anomaly`
+        owner_rescan: Mutex<Option<Instant>>,
+        config: RwLock<Config>,
+        // Where `set_config` saves; `None` until the file is loaded
+        config_path: RwLock<Option<PathBuf>>,
+        // The models shipped with the app, used while `paths.models_dir` is unset
+        bundled_models_dir: RwLock<PathBuf>,
+        monitor_wake: Wakeup,
+        // What the frontend last received, for network_delta events
+        network_changes: ChangeTracker,
+        pipeline: PipelineCounters,
+        scans: ScanDetector,
+        beacons: BeaconDetector,
+        dns: DnsDetector,
+        dns_log: DnsLog,
+        tls_blocklist: TlsBlocklist,
+        arp: ArpMonitor,
+        // `analyze_capture` calls still running
+        analyses: Analyses,
     }
 }
 
+// Rates in Mbps over the last sample interval, smoothed and peak
 DefaultStruct! {
     pub struct Bandwidth {
         download: f64,
         upload: f64,
+        download_avg: f64,
+        upload_avg: f64,
+        peak_download: f64,
+        peak_upload: f64,
+    }
+}
+
+impl Bandwidth {
+    pub fn new() -> Self {
+        Self {
+            download: 0.0,
+            upload: 0.0,
+            download_avg: 0.0,
+            upload_avg: 0.0,
+            peak_download: 0.0,
+            peak_upload: 0.0,
+        }
+    }
+}
+
+DefaultStruct! {
+    pub struct BandwidthSample {
+        timestamp: DateTime<Utc>,
+        download: f64,
+        upload: f64,
     }
 }
 
@@ -25,6 +102,34 @@ DefaultStruct! {
     pub struct IPInfo {
         ipv4: Option<String>,
         ipv6: Option<String>,
+        ipv4_addrs: Vec<String>,
+        ipv6_addrs: Vec<String>,
+    }
+}
+
+// One socket; remote endpoint and state are only set for TCP
+DefaultStruct! {
+    pub struct Connection {
+        protocol: String,
+        local_addr: String,
+        local_port: u16,
+        remote_addr: Option<String>,
+        remote_port: Option<u16>,
+        state: Option<String>,
+        pid: Option<u32>,
+        process: Option<String>,
+    }
+}
+
+DefaultStruct! {
+    pub struct ProcessInfo {
+        pid: u32,
+        name: String,
+        exe: Option<String>,
+        user: Option<String>,
+        cmd: Vec<String>,
+        cpu_usage: f32,
+        connections: Vec<Connection>,
     }
 }
 
@@ -48,13 +153,18 @@ DefaultStruct! {
     pub struct NetworkInfo {
         id: String,
         name: String,
+        index: u32,
+        mac: Option<String>,
         status: String,
+        monitoring_state: MonitoringState,
         anomaly: AnomalyDetection,
         ip_info: IPInfo,
         bandwidth: Bandwidth,
+        bandwidth_history: Vec<BandwidthSample>,
         received_bytes: u64,
         transmitted_bytes: u64,
         active_ports: Vec<u16>,
+        connections: Vec<Connection>,
         last_seen: DateTime<Utc>,
         cpu_usage: f32,
     }
@@ -0,0 +1,238 @@
+use crate::modules::capture::{CaptureOptions, LiveCapture};
+use crate::modules::pipeline::Pipeline;
+use crate::{spawn_worker, AppState, DefaultStruct};
+
+use crate::modules::protocol::WorkerMessage;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ERROR_TAIL: usize = 20;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const BASE_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+// A worker that stays up this long is considered healthy again
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+DefaultStruct! {
+    pub struct WorkerHealth {
+        key: String,
+        iface: String,
+        status: String,
+        uptime_secs: u64,
+        restarts: u32,
+        crash_count: u32,
+        last_exit: Option<String>,
+        next_restart_in_secs: Option<u64>,
+        last_errors: Vec<String>,
+        messages: u64,
+        last_heartbeat: Option<DateTime<Utc>>,
+    }
+}
+
+/// What a capture thread records about the messages it sends.
+/// Shared across restarts so counters survive crashes.
+#[derive(Debug, Default)]
+pub struct WorkerOutput {
+    messages: AtomicU64,
+    last_heartbeat: Mutex<Option<DateTime<Utc>>>,
+    // Verdicts are dropped while set; the worker keeps capturing
+    paused: AtomicBool,
+}
+
+impl WorkerOutput {
+    pub fn record(&self, message: &WorkerMessage) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+
+        if let WorkerMessage::Heartbeat(beat) = message {
+            *self.last_heartbeat.lock().unwrap() = Some(beat.timestamp);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+}
+
+/// One supervised live capture and its crash history.
+#[derive(Debug)]
+pub struct Worker {
+    iface_name: String,
+    // Used on every (re)start
+    options: CaptureOptions,
+    capture: Option<LiveCapture>,
+    output: Arc<WorkerOutput>,
+    // Why the capture ended, oldest first, timestamped
+    error_tail: VecDeque<String>,
+    started_at: Instant,
+    restarts: u32,
+    crash_count: u32,
+    backoff: Duration,
+    next_restart: Option<Instant>,
+    last_exit: Option<String>,
+}
+
+impl Worker {
+    pub fn start(
+        iface_name: &str,
+        options: CaptureOptions,
+        pipeline: &Pipeline,
+        state: &Arc<AppState>,
+    ) -> Self {
+        let mut worker = Self {
+            iface_name: iface_name.to_string(),
+            options,
+            capture: None,
+            output: Arc::new(WorkerOutput::default()),
+            error_tail: VecDeque::with_capacity(ERROR_TAIL),
+            started_at: Instant::now(),
+            restarts: 0,
+            crash_count: 0,
+            backoff: BASE_BACKOFF,
+            next_restart: None,
+            last_exit: None,
+        };
+
+        worker.spawn(pipeline, state);
+        worker
+    }
+
+    fn spawn(&mut self, pipeline: &Pipeline, state: &Arc<AppState>) {
+        let output = Arc::clone(&self.output);
+
+        match spawn_worker(pipeline, state, &self.iface_name, &self.options, output) {
+            Some(capture) => {
+                self.capture = Some(capture);
+                self.started_at = Instant::now();
+                self.next_restart = None;
+            }
+            None => self.schedule_restart("failed to start".into()),
+        }
+    }
+
+    fn record_error(&mut self, error: &str) {
+        if self.error_tail.len() == ERROR_TAIL {
+            self.error_tail.pop_front();
+        }
+        self.error_tail
+            .push_back(format!("{} {error}", Utc::now().to_rfc3339()));
+    }
+
+    fn schedule_restart(&mut self, reason: String) {
+        eprintln!(
+            "worker '{}' down ({reason}), restarting in {:?}",
+            self.iface_name, self.backoff
+        );
+
+        self.record_error(&reason);
+        self.crash_count += 1;
+        self.last_exit = Some(reason);
+        self.next_restart = Some(Instant::now() + self.backoff);
+        self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+    }
+
+    /// Reaps a capture that ended and restarts it once its backoff has elapsed.
+    pub fn poll(&mut self, pipeline: &Pipeline, state: &Arc<AppState>) {
+        if let Some(capture) = self.capture.take_if(|capture| capture.is_finished()) {
+            let reason = match capture.stop() {
+                Ok(()) => "capture ended".to_string(),
+                Err(e) => e,
+            };
+            self.schedule_restart(reason);
+        } else if self.capture.is_some() {
+            if self.started_at.elapsed() >= STABLE_AFTER {
+                self.backoff = BASE_BACKOFF;
+            }
+        } else if self.next_restart.is_some_and(|at| Instant::now() >= at) {
+            self.restarts += 1;
+            self.spawn(pipeline, state);
+        }
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.output.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Stops the capture and waits, up to a bound, for its thread to release
+    /// the device before anything replaces it.
+    pub fn stop(&mut self) {
+        if let Some(capture) = self.capture.take() {
+            if let Err(e) = capture.stop() {
+                eprintln!("worker '{}': {e}", self.iface_name);
+                self.record_error(&e);
+            }
+        }
+        self.next_restart = None;
+    }
+
+    pub fn health(&self, key: &str) -> WorkerHealth {
+        let status = if self.capture.is_some() && self.output.is_paused() {
+            "paused"
+        } else if self.capture.is_some() {
+            "running"
+        } else if self.next_restart.is_some() {
+            "backoff"
+        } else {
+            "stopped"
+        };
+
+        WorkerHealth {
+            key: key.to_string(),
+            iface: self.iface_name.clone(),
+            status: status.into(),
+            uptime_secs: if self.capture.is_some() {
+                self.started_at.elapsed().as_secs()
+            } else {
+                0
+            },
+            restarts: self.restarts,
+            crash_count: self.crash_count,
+            last_exit: self.last_exit.clone(),
+            next_restart_in_secs: self
+                .next_restart
+                .map(|at| at.saturating_duration_since(Instant::now()).as_secs()),
+            last_errors: self.error_tail.iter().cloned().collect(),
+            messages: self.output.messages.load(Ordering::Relaxed),
+            last_heartbeat: *self.output.last_heartbeat.lock().unwrap(),
+        }
+    }
+}
+
+pub fn worker_health(state: &AppState) -> Vec<WorkerHealth> {
+    let workers = state.workers.lock().unwrap();
+
+    let mut health: Vec<WorkerHealth> = workers
+        .iter()
+        .map(|(key, worker)| worker.health(key))
+        .collect();
+    health.sort_by(|a, b| a.key.cmp(&b.key));
+
+    health
+}
+
+pub fn stop_workers(state: &AppState) {
+    let mut workers = state.workers.lock().unwrap();
+
+    for (_, mut worker) in workers.drain() {
+        worker.stop();
+    }
+}
+
+pub fn supervise(pipeline: Pipeline, state: Arc<AppState>) {
+    thread::spawn(move || loop {
+        {
+            let mut workers = state.workers.lock().unwrap();
+            for worker in workers.values_mut() {
+                worker.poll(&pipeline, &state);
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    });
+}
@@ -0,0 +1,711 @@
+use crate::modules::helpers::to_hex;
+use crate::modules::protocol::{ModelSource, Verdict};
+
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+// Enough for a ClientHello with post-quantum key shares
+pub const MAX_TLS_BYTES: usize = 4096;
+
+const RECORD_HANDSHAKE: u8 = 0x16;
+const CLIENT_HELLO: u8 = 1;
+const SERVER_HELLO: u8 = 2;
+
+// ---- Extension types ----
+const EXT_SNI: u16 = 0;
+const EXT_GROUPS: u16 = 10;
+const EXT_POINT_FORMATS: u16 = 11;
+const EXT_SIGNATURE_ALGORITHMS: u16 = 13;
+const EXT_ALPN: u16 = 16;
+const EXT_SUPPORTED_VERSIONS: u16 = 43;
+
+// Blocklist edits are picked up at most this often
+const BLOCKLIST_CHECK: Duration = Duration::from_secs(5);
+
+/// What the hellos of a TLS connection reveal. `version` is the negotiated
+/// one when the ServerHello was seen, the client's highest otherwise.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TlsInfo {
+    pub version: Option<String>,
+    pub sni: Option<String>,
+    // Offered by the client, in its order
+    pub alpn: Vec<String>,
+    pub cipher_suites: Vec<u16>,
+    // Chosen by the server
+    pub cipher: Option<u16>,
+    pub ja3: Option<String>,
+    pub ja3s: Option<String>,
+    pub ja4: Option<String>,
+    // Blocklist entry one of the fingerprints matched
+    #[serde(default)]
+    pub blocklisted: Option<String>,
+}
+
+// ---------------------------------------------------------------------
+// HELLO PARSING
+// ---------------------------------------------------------------------
+struct Reader<'a> {
+    data: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, at: 0 }
+    }
+
+    fn bytes(&mut self, n: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.at..self.at + n)?;
+        self.at += n;
+        Some(bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        let b = self.bytes(2)?;
+        Some(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    // Length-prefixed vectors
+    fn vec8(&mut self) -> Option<&'a [u8]> {
+        let len = self.u8()? as usize;
+        self.bytes(len)
+    }
+
+    fn vec16(&mut self) -> Option<&'a [u8]> {
+        let len = self.u16()? as usize;
+        self.bytes(len)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.at >= self.data.len()
+    }
+}
+
+fn u16s(data: &[u8]) -> Vec<u16> {
+    data.chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect()
+}
+
+/// GREASE values (RFC 8701) are random filler and left out of fingerprints.
+fn is_grease(value: u16) -> bool {
+    value & 0x0F0F == 0x0A0A && value >> 8 == value & 0xFF
+}
+
+fn not_grease(values: &[u16]) -> Vec<u16> {
+    values.iter().copied().filter(|v| !is_grease(*v)).collect()
+}
+
+/// Reassembles the first handshake message from the records at the start
+/// of a stream, and returns its body if it is of type `kind`.
+fn handshake(stream: &[u8], kind: u8) -> Option<Vec<u8>> {
+    let mut records = Reader::new(stream);
+    let mut message = Vec::new();
+
+    while !records.is_empty() {
+        if records.u8()? != RECORD_HANDSHAKE {
+            break;
+        }
+        records.u16()?;
+        let len = records.u16()? as usize;
+        // The last record may be cut short by the capture
+        let rest = &records.data[records.at..];
+        let fragment = &rest[..len.min(rest.len())];
+        records.at += fragment.len();
+        message.extend_from_slice(fragment);
+
+        if message.len() >= 4 {
+            let body_len = u32::from_be_bytes([0, message[1], message[2], message[3]]) as usize;
+            if message.len() >= 4 + body_len {
+                message.truncate(4 + body_len);
+                break;
+            }
+        }
+    }
+
+    if message.first() != Some(&kind) || message.len() < 4 {
+        return None;
+    }
+    message.drain(..4);
+    Some(message)
+}
+
+#[derive(Debug, Default)]
+struct ClientHello {
+    version: u16,
+    ciphers: Vec<u16>,
+    extensions: Vec<u16>,
+    groups: Vec<u16>,
+    point_formats: Vec<u8>,
+    signature_algorithms: Vec<u16>,
+    supported_versions: Vec<u16>,
+    sni: Option<String>,
+    alpn: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Default)]
+struct ServerHello {
+    version: u16,
+    cipher: u16,
+    extensions: Vec<u16>,
+    selected_version: Option<u16>,
+}
+
+/// Walks an extension block, handing each type and body to `on_extension`.
+fn extensions(r: &mut Reader, mut on_extension: impl FnMut(u16, &[u8])) -> Option<()> {
+    // Hellos without extensions end right after the fixed fields
+    if r.is_empty() {
+        return Some(());
+    }
+
+    let mut block = Reader::new(r.vec16()?);
+    while !block.is_empty() {
+        let kind = block.u16()?;
+        on_extension(kind, block.vec16()?);
+    }
+    Some(())
+}
+
+fn parse_client_hello(body: &[u8]) -> Option<ClientHello> {
+    let mut r = Reader::new(body);
+    let mut hello = ClientHello {
+        version: r.u16()?,
+        ..Default::default()
+    };
+    r.bytes(32)?; // random
+    r.vec8()?; // session id
+    hello.ciphers = u16s(r.vec16()?);
+    r.vec8()?; // compression methods
+
+    extensions(&mut r, |kind, data| {
+        hello.extensions.push(kind);
+        let mut d = Reader::new(data);
+        match kind {
+            EXT_SNI => {
+                // Only host names are defined, take the first
+                let name = d.vec16().and_then(|list| {
+                    let mut list = Reader::new(list);
+                    let kind = list.u8()?;
+                    let name = list.vec16()?;
+                    (kind == 0).then_some(name)
+                });
+                hello.sni = name.map(|name| String::from_utf8_lossy(name).to_lowercase());
+            }
+            EXT_ALPN => {
+                if let Some(list) = d.vec16() {
+                    let mut list = Reader::new(list);
+                    while let Some(proto) = list.vec8() {
+                        hello.alpn.push(proto.to_vec());
+                    }
+                }
+            }
+            EXT_GROUPS => hello.groups = d.vec16().map(u16s).unwrap_or_default(),
+            EXT_POINT_FORMATS => hello.point_formats = d.vec8().unwrap_or_default().to_vec(),
+            EXT_SIGNATURE_ALGORITHMS => {
+                hello.signature_algorithms = d.vec16().map(u16s).unwrap_or_default();
+            }
+            EXT_SUPPORTED_VERSIONS => {
+                hello.supported_versions = d.vec8().map(u16s).unwrap_or_default();
+            }
+            _ => {}
+        }
+    })?;
+
+    Some(hello)
+}
+
+fn parse_server_hello(body: &[u8]) -> Option<ServerHello> {
+    let mut r = Reader::new(body);
+    let mut hello = ServerHello {
+        version: r.u16()?,
+        ..Default::default()
+    };
+    r.bytes(32)?; // random
+    r.vec8()?; // session id
+    hello.cipher = r.u16()?;
+    r.u8()?; // compression method
+
+    extensions(&mut r, |kind, data| {
+        hello.extensions.push(kind);
+        if kind == EXT_SUPPORTED_VERSIONS {
+            hello.selected_version = Reader::new(data).u16();
+        }
+    })?;
+
+    Some(hello)
+}
+
+// ---------------------------------------------------------------------
+// FINGERPRINTS
+// ---------------------------------------------------------------------
+fn join<T: ToString>(values: &[T], sep: &str) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+fn hex4(values: &[u16]) -> Vec<String> {
+    values.iter().map(|v| format!("{v:04x}")).collect()
+}
+
+fn sha256_12(text: &str) -> String {
+    to_hex(&Sha256::digest(text.as_bytes()))[..12].to_string()
+}
+
+fn ja3(hello: &ClientHello) -> String {
+    let text = format!(
+        "{},{},{},{},{}",
+        hello.version,
+        join(&not_grease(&hello.ciphers), "-"),
+        join(&not_grease(&hello.extensions), "-"),
+        join(&not_grease(&hello.groups), "-"),
+        join(&hello.point_formats, "-"),
+    );
+    to_hex(&Md5::digest(text.as_bytes()))
+}
+
+fn ja3s(hello: &ServerHello) -> String {
+    let text = format!(
+        "{},{},{}",
+        hello.version,
+        hello.cipher,
+        join(&hello.extensions, "-"),
+    );
+    to_hex(&Md5::digest(text.as_bytes()))
+}
+
+/// JA4 of a ClientHello over TCP, per the FoxIO specification.
+fn ja4(hello: &ClientHello) -> String {
+    let version = not_grease(&hello.supported_versions)
+        .into_iter()
+        .max()
+        .unwrap_or(hello.version);
+    let version = match version {
+        0x0304 => "13",
+        0x0303 => "12",
+        0x0302 => "11",
+        0x0301 => "10",
+        0x0300 => "s3",
+        0x0002 => "s2",
+        _ => "00",
+    };
+    let destination = if hello.sni.is_some() { 'd' } else { 'i' };
+
+    let ciphers = not_grease(&hello.ciphers);
+    let extensions = not_grease(&hello.extensions);
+
+    // First and last character of the first ALPN value, or of its hex form
+    let alpn = match hello.alpn.first().filter(|p| !p.is_empty()) {
+        Some(proto) => {
+            let (first, last) = (proto[0], proto[proto.len() - 1]);
+            if first.is_ascii_alphanumeric() && last.is_ascii_alphanumeric() {
+                format!("{}{}", first as char, last as char)
+            } else {
+                let hex = to_hex(proto);
+                format!("{}{}", &hex[..1], &hex[hex.len() - 1..])
+            }
+        }
+        None => "00".into(),
+    };
+
+    let prefix = format!(
+        "t{version}{destination}{:02}{:02}{alpn}",
+        ciphers.len().min(99),
+        extensions.len().min(99),
+    );
+
+    let mut sorted_ciphers = hex4(&ciphers);
+    sorted_ciphers.sort();
+    let cipher_hash = match sorted_ciphers.is_empty() {
+        true => "000000000000".into(),
+        false => sha256_12(&sorted_ciphers.join(",")),
+    };
+
+    // SNI and ALPN are already in the prefix
+    let hashed: Vec<u16> = extensions
+        .iter()
+        .copied()
+        .filter(|kind| !matches!(*kind, EXT_SNI | EXT_ALPN))
+        .collect();
+    let mut sorted_extensions = hex4(&hashed);
+    sorted_extensions.sort();
+    let mut text = sorted_extensions.join(",");
+    if !hello.signature_algorithms.is_empty() {
+        text.push('_');
+        text.push_str(&hex4(&hello.signature_algorithms).join(","));
+    }
+    let extension_hash = match sorted_extensions.is_empty() {
+        true => "000000000000".into(),
+        false => sha256_12(&text),
+    };
+
+    format!("{prefix}_{cipher_hash}_{extension_hash}")
+}
+
+fn version_name(version: u16) -> String {
+    match version {
+        0x0304 => "TLS 1.3".into(),
+        0x0303 => "TLS 1.2".into(),
+        0x0302 => "TLS 1.1".into(),
+        0x0301 => "TLS 1.0".into(),
+        0x0300 => "SSL 3.0".into(),
+        other => format!("0x{other:04x}"),
+    }
+}
+
+/// Reads the hellos at the start of a TCP connection. `client` is what the
+/// initiator sent, `server` the reply and may be empty.
+pub fn inspect(client: &[u8], server: &[u8]) -> Option<TlsInfo> {
+    let hello = parse_client_hello(&handshake(client, CLIENT_HELLO)?)?;
+    let reply = handshake(server, SERVER_HELLO).and_then(|body| parse_server_hello(&body));
+
+    let version = match &reply {
+        Some(reply) => reply.selected_version.unwrap_or(reply.version),
+        None => not_grease(&hello.supported_versions)
+            .into_iter()
+            .max()
+            .unwrap_or(hello.version),
+    };
+
+    Some(TlsInfo {
+        version: Some(version_name(version)),
+        sni: hello.sni.clone(),
+        alpn: hello
+            .alpn
+            .iter()
+            .map(|p| String::from_utf8_lossy(p).to_string())
+            .collect(),
+        cipher_suites: not_grease(&hello.ciphers),
+        cipher: reply.as_ref().map(|r| r.cipher),
+        ja3: Some(ja3(&hello)),
+        ja3s: reply.as_ref().map(ja3s),
+        ja4: Some(ja4(&hello)),
+        blocklisted: None,
+    })
+}
+
+/// Appends a TCP payload to the start of one direction of a stream.
+/// `None` once the direction turned out not to open with a handshake.
+pub fn collect(buffer: &mut Option<Vec<u8>>, payload: &[u8]) {
+    let Some(data) = buffer else {
+        return;
+    };
+
+    if data.is_empty() && payload.first() != Some(&RECORD_HANDSHAKE) {
+        *buffer = None;
+        return;
+    }
+    let room = MAX_TLS_BYTES.saturating_sub(data.len());
+    data.extend_from_slice(&payload[..payload.len().min(room)]);
+}
+
+// ---------------------------------------------------------------------
+// BLOCKLIST
+// ---------------------------------------------------------------------
+#[derive(Debug, Default)]
+struct BlocklistInner {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    checked: Option<Instant>,
+    // Fingerprint → description
+    entries: HashMap<String, String>,
+}
+
+impl BlocklistInner {
+    fn refresh(&mut self, path: Option<&Path>) {
+        let Some(path) = path else {
+            *self = Self::default();
+            return;
+        };
+
+        let same = self.path.as_deref() == Some(path);
+        if same
+            && self
+                .checked
+                .is_some_and(|at| at.elapsed() < BLOCKLIST_CHECK)
+        {
+            return;
+        }
+        self.checked = Some(Instant::now());
+
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+        if same && modified == self.modified {
+            return;
+        }
+
+        self.path = Some(path.to_path_buf());
+        self.modified = modified;
+        self.entries = fs::read_to_string(path)
+            .inspect_err(|e| eprintln!("TLS blocklist {}: {e}", path.display()))
+            .map(|text| parse_blocklist(&text))
+            .unwrap_or_default();
+    }
+}
+
+/// One fingerprint per line, optionally followed by whitespace or a comma
+/// and a description. `#` starts a comment.
+fn parse_blocklist(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let separator = |c: char| c.is_whitespace() || c == ',';
+            let (fingerprint, description) = line.split_once(separator).unwrap_or((line, ""));
+            let description = description.trim_start_matches(separator).trim();
+            (fingerprint.to_lowercase(), description.to_string())
+        })
+        .collect()
+}
+
+/// Local list of JA3, JA3S and JA4 fingerprints to alert on, re-read
+/// when the file changes.
+#[derive(Debug, Default)]
+pub struct TlsBlocklist {
+    inner: Mutex<BlocklistInner>,
+}
+
+impl TlsBlocklist {
+    /// The first fingerprint of `tls` listed in the file at `path`, with
+    /// its description.
+    pub fn lookup(&self, path: Option<&Path>, tls: &TlsInfo) -> Option<String> {
+        let mut inner = self.inner.lock().unwrap();
+        inner.refresh(path);
+
+        [&tls.ja3, &tls.ja3s, &tls.ja4]
+            .into_iter()
+            .flatten()
+            .find_map(|fingerprint| {
+                let description = inner.entries.get(fingerprint)?;
+                Some(match description.is_empty() {
+                    true => fingerprint.clone(),
+                    false => format!("{fingerprint} ({description})"),
+                })
+            })
+    }
+}
+
+/// An anomalous copy of a verdict whose TLS fingerprint is blocklisted.
+pub fn blocklist_alert(verdict: &Verdict) -> Option<Verdict> {
+    verdict.tls.as_ref()?.blocklisted.as_ref()?;
+
+    Some(Verdict {
+        label: "TLS_BLOCKLIST".into(),
+        is_anomaly: true,
+        score: 1.0,
+        model: Some(ModelSource::Tls),
+        ..verdict.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vec8(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![data.len() as u8];
+        out.extend(data);
+        out
+    }
+
+    fn vec16(data: &[u8]) -> Vec<u8> {
+        let mut out = (data.len() as u16).to_be_bytes().to_vec();
+        out.extend(data);
+        out
+    }
+
+    fn be16(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    fn extension(kind: u16, body: &[u8]) -> Vec<u8> {
+        let mut out = kind.to_be_bytes().to_vec();
+        out.extend(vec16(body));
+        out
+    }
+
+    fn message(kind: u8, body: &[u8]) -> Vec<u8> {
+        let len = (body.len() as u32).to_be_bytes();
+        let mut out = vec![kind, len[1], len[2], len[3]];
+        out.extend(body);
+        out
+    }
+
+    // One record per fragment
+    fn records(message: &[u8], fragments: &[usize]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut rest = message;
+        for &len in fragments.iter().chain([&rest.len()]) {
+            let (fragment, tail) = rest.split_at(len.min(rest.len()));
+            out.extend([RECORD_HANDSHAKE, 3, 1]);
+            out.extend(vec16(fragment));
+            rest = tail;
+        }
+        out
+    }
+
+    // Chrome's ClientHello, GREASE included: its JA3 and JA4 are the
+    // published ones. `padding` bytes go in the padding extension.
+    fn client_hello(padding: usize) -> Vec<u8> {
+        let ciphers = [
+            0x1a1a, 0x1301, 0x1302, 0x1303, 0xc02b, 0xc02f, 0xc02c, 0xc030, 0xcca9, 0xcca8, 0xc013,
+            0xc014, 0x009c, 0x009d, 0x002f, 0x0035,
+        ];
+        let mut sni = vec![0];
+        sni.extend(vec16(b"Example.COM"));
+        let mut alpn = vec8(b"h2");
+        alpn.extend(vec8(b"http/1.1"));
+        let signatures = [
+            0x0403, 0x0804, 0x0401, 0x0503, 0x0805, 0x0501, 0x0806, 0x0601,
+        ];
+
+        let extensions = [
+            extension(0x2a2a, &[]),
+            extension(EXT_SNI, &vec16(&sni)),
+            extension(0x0017, &[]),
+            extension(0xff01, &[0]),
+            extension(EXT_GROUPS, &vec16(&be16(&[0x3a3a, 0x001d, 0x0017, 0x0018]))),
+            extension(EXT_POINT_FORMATS, &vec8(&[0])),
+            extension(0x0023, &[]),
+            extension(EXT_ALPN, &vec16(&alpn)),
+            extension(0x0005, &[1, 0, 0, 0, 0]),
+            extension(EXT_SIGNATURE_ALGORITHMS, &vec16(&be16(&signatures))),
+            extension(0x0012, &[]),
+            extension(0x0033, &vec16(&[])),
+            extension(0x002d, &vec8(&[1])),
+            extension(
+                EXT_SUPPORTED_VERSIONS,
+                &vec8(&be16(&[0x4a4a, 0x0304, 0x0303])),
+            ),
+            extension(0x001b, &vec16(&[0, 2])),
+            extension(0x4469, &[]),
+            extension(0x0015, &vec![0; padding]),
+        ]
+        .concat();
+
+        let mut body = vec![3, 3];
+        body.extend([7; 32]);
+        body.extend(vec8(&[9; 32]));
+        body.extend(vec16(&be16(&ciphers)));
+        body.extend(vec8(&[0]));
+        body.extend(vec16(&extensions));
+        message(CLIENT_HELLO, &body)
+    }
+
+    // A TLS 1.3 ServerHello choosing TLS_AES_128_GCM_SHA256
+    fn server_hello() -> Vec<u8> {
+        let extensions = [
+            extension(EXT_SUPPORTED_VERSIONS, &0x0304u16.to_be_bytes()),
+            extension(0x0033, &[0; 4]),
+        ]
+        .concat();
+
+        let mut body = vec![3, 3];
+        body.extend([1; 32]);
+        body.extend(vec8(&[]));
+        body.extend(0x1301u16.to_be_bytes());
+        body.push(0);
+        body.extend(vec16(&extensions));
+        message(SERVER_HELLO, &body)
+    }
+
+    // What `collect` keeps of a stream sent in `segments`
+    fn collected(stream: &[u8], segment: usize) -> Vec<u8> {
+        let mut buffer = Some(Vec::new());
+        for chunk in stream.chunks(segment) {
+            collect(&mut buffer, chunk);
+        }
+        buffer.unwrap()
+    }
+
+    #[test]
+    fn fingerprints_a_client_and_server_hello() {
+        let client = records(&client_hello(10), &[]);
+        let server = records(&server_hello(), &[]);
+
+        let info = inspect(&client, &server).unwrap();
+        assert_eq!(
+            info.ja3.as_deref(),
+            Some("cd08e31494f9531f560d64c695473da9")
+        );
+        assert_eq!(
+            info.ja3s.as_deref(),
+            Some("f4febc55ea12b31ae17cfb7e614afda8")
+        );
+        assert_eq!(
+            info.ja4.as_deref(),
+            Some("t13d1516h2_8daaf6152771_e5627efa2ab1")
+        );
+        assert_eq!(info.sni.as_deref(), Some("example.com"));
+        assert_eq!(info.alpn, vec!["h2", "http/1.1"]);
+        assert_eq!(info.version.as_deref(), Some("TLS 1.3"));
+        assert_eq!(info.cipher, Some(0x1301));
+        assert_eq!(info.cipher_suites.len(), 15);
+    }
+
+    #[test]
+    fn client_hello_alone_has_no_ja3s() {
+        let info = inspect(&records(&client_hello(10), &[]), &[]).unwrap();
+
+        assert_eq!(
+            info.ja3.as_deref(),
+            Some("cd08e31494f9531f560d64c695473da9")
+        );
+        assert_eq!(info.ja3s, None);
+        assert_eq!(info.cipher, None);
+        // The client's highest offer
+        assert_eq!(info.version.as_deref(), Some("TLS 1.3"));
+    }
+
+    #[test]
+    fn reassembles_a_hello_split_across_records_and_segments() {
+        let whole = inspect(&records(&client_hello(10), &[]), &[]).unwrap();
+
+        // Two records, the first ending mid-hello, sent in small segments
+        let split = records(&client_hello(10), &[50]);
+        assert_eq!(inspect(&split, &[]), Some(whole.clone()));
+        assert_eq!(inspect(&collected(&split, 30), &[]), Some(whole.clone()));
+
+        // The first record too short to hold the message length
+        let split = records(&client_hello(10), &[2]);
+        assert_eq!(inspect(&split, &[]), Some(whole));
+    }
+
+    #[test]
+    fn hello_cut_at_max_tls_bytes_is_not_fingerprinted() {
+        let stream = records(&client_hello(MAX_TLS_BYTES), &[]);
+        let kept = collected(&stream, 1460);
+        assert_eq!(kept.len(), MAX_TLS_BYTES);
+
+        // Fingerprints of the extensions that fit would be wrong
+        assert_eq!(inspect(&kept, &[]), None);
+
+        // Right up to the limit is fine
+        let fits = MAX_TLS_BYTES - (stream.len() - MAX_TLS_BYTES) - 1;
+        let stream = records(&client_hello(fits), &[]);
+        assert_eq!(stream.len(), MAX_TLS_BYTES - 1);
+        let info = inspect(&collected(&stream, 1460), &[]).unwrap();
+        assert_eq!(
+            info.ja4.as_deref(),
+            Some("t13d1516h2_8daaf6152771_e5627efa2ab1")
+        );
+    }
+
+    #[test]
+    fn only_handshakes_are_collected() {
+        let mut buffer = Some(Vec::new());
+        collect(&mut buffer, b"GET / HTTP/1.1\r\n");
+        assert_eq!(buffer, None);
+    }
+}
@@ -0,0 +1,517 @@
+use crate::modules::flows::{xgb_feature_names, XGB_FEATURE_COUNT};
+use crate::modules::scaler::StandardScaler;
+use crate::AnomalyDetection;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+// ---------------------------------------------------------------------
+// XGBoost JSON model schema (`booster.save_model("model.json")`)
+// ---------------------------------------------------------------------
+#[derive(Deserialize)]
+struct ModelFile {
+    learner: LearnerJson,
+}
+
+#[derive(Deserialize)]
+struct LearnerJson {
+    gradient_booster: GradientBoosterJson,
+    learner_model_param: LearnerModelParamJson,
+    objective: ObjectiveJson,
+}
+
+// XGBoost serialises every model parameter as a string
+#[derive(Deserialize)]
+struct LearnerModelParamJson {
+    base_score: String,
+    num_class: String,
+    num_feature: String,
+}
+
+#[derive(Deserialize)]
+struct ObjectiveJson {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct GradientBoosterJson {
+    name: String,
+    model: Option<GbTreeJson>,
+}
+
+#[derive(Deserialize)]
+struct GbTreeJson {
+    trees: Vec<TreeJson>,
+    tree_info: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+struct TreeJson {
+    left_children: Vec<i32>,
+    right_children: Vec<i32>,
+    split_indices: Vec<usize>,
+    split_conditions: Vec<f32>,
+    default_left: Vec<FlagJson>,
+    #[serde(default)]
+    split_type: Vec<u8>,
+}
+
+// Older releases write `default_left` as 0/1, newer ones as booleans
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum FlagJson {
+    Int(u8),
+    Bool(bool),
+}
+
+impl FlagJson {
+    fn is_set(&self) -> bool {
+        match self {
+            FlagJson::Int(v) => *v != 0,
+            FlagJson::Bool(v) => *v,
+        }
+    }
+}
+
+// ---------------------------------------------------------------------
+// TREE ENSEMBLE
+// ---------------------------------------------------------------------
+struct Tree {
+    left: Vec<i32>,
+    right: Vec<i32>,
+    feature: Vec<usize>,
+    // Split threshold for inner nodes, leaf value for leaves
+    value: Vec<f32>,
+    default_left: Vec<bool>,
+}
+
+impl Tree {
+    fn from_json(tree: TreeJson, num_feature: usize) -> Result<Self, String> {
+        let nodes = tree.left_children.len();
+
+        if nodes == 0 {
+            return Err("Tree has no nodes".into());
+        }
+
+        if tree.right_children.len() != nodes
+            || tree.split_indices.len() != nodes
+            || tree.split_conditions.len() != nodes
+            || tree.default_left.len() != nodes
+        {
+            return Err("Tree arrays have mismatched lengths".into());
+        }
+
+        if tree.split_type.iter().any(|t| *t != 0) {
+            return Err("Categorical splits are not supported".into());
+        }
+
+        // With one parent per node and none for the root, every path from
+        // the root ends in a leaf, so scoring can't loop
+        let mut has_parent = vec![false; nodes];
+
+        for node in 0..nodes {
+            let (left, right) = (tree.left_children[node], tree.right_children[node]);
+            if left == -1 {
+                continue;
+            }
+            if left < 0 || left as usize >= nodes || right < 0 || right as usize >= nodes {
+                return Err(format!("Node {node} points outside the tree"));
+            }
+            if tree.split_indices[node] >= num_feature {
+                return Err(format!("Node {node} splits on unknown feature"));
+            }
+
+            for child in [left as usize, right as usize] {
+                if child == 0 || has_parent[child] {
+                    return Err(format!("Node {child} is reached twice, the tree has a cycle"));
+                }
+                has_parent[child] = true;
+            }
+        }
+
+        Ok(Self {
+            left: tree.left_children,
+            right: tree.right_children,
+            feature: tree.split_indices,
+            value: tree.split_conditions,
+            default_left: tree.default_left.iter().map(FlagJson::is_set).collect(),
+        })
+    }
+
+    fn leaf(&self, row: &[f32]) -> f32 {
+        let mut node = 0;
+
+        while self.left[node] != -1 {
+            let x = row[self.feature[node]];
+
+            let go_left = if x.is_nan() {
+                self.default_left[node]
+            } else {
+                x < self.value[node]
+            };
+
+            node = if go_left { self.left[node] } else { self.right[node] } as usize;
+        }
+
+        self.value[node]
+    }
+}
+
+pub struct Booster {
+    trees: Vec<Tree>,
+    tree_class: Vec<usize>,
+    base_score: Vec<f32>,
+    num_class: usize,
+    num_feature: usize,
+}
+
+impl Booster {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let raw = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+
+        Self::from_json(&raw).map_err(|e| format!("Invalid XGBoost model {}: {e}", path.display()))
+    }
+
+    fn from_json(raw: &str) -> Result<Self, String> {
+        let file: ModelFile = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+
+        let learner = file.learner;
+        let params = learner.learner_model_param;
+
+        if !learner.objective.name.starts_with("multi:") {
+            return Err(format!(
+                "Expected a multi-class objective, found '{}'",
+                learner.objective.name
+            ));
+        }
+
+        let model = match (learner.gradient_booster.name.as_str(), learner.gradient_booster.model) {
+            ("gbtree", Some(model)) => model,
+            (name, _) => return Err(format!("Unsupported booster '{name}'")),
+        };
+
+        let num_class: usize = params.num_class.parse().map_err(|_| "Invalid num_class")?;
+        let num_feature: usize = params.num_feature.parse().map_err(|_| "Invalid num_feature")?;
+
+        if num_class < 2 {
+            return Err(format!("Expected a multi-class model, found {num_class} classes"));
+        }
+        if model.tree_info.len() != model.trees.len() || model.tree_info.iter().any(|c| *c >= num_class) {
+            return Err("tree_info does not match the trees".into());
+        }
+
+        // "5E-1" in 1.x, "[5E-1]" or one value per class in 2.x+
+        let base_score: Vec<f32> = params
+            .base_score
+            .trim_matches(|c| c == '[' || c == ']')
+            .split(',')
+            .map(|v| v.trim().parse::<f32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid base_score '{}'", params.base_score))?;
+
+        let base_score = match base_score.len() {
+            1 => vec![base_score[0]; num_class],
+            n if n == num_class => base_score,
+            _ => return Err("base_score does not match num_class".into()),
+        };
+
+        let trees = model
+            .trees
+            .into_iter()
+            .map(|tree| Tree::from_json(tree, num_feature))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            trees,
+            tree_class: model.tree_info,
+            base_score,
+            num_class,
+            num_feature,
+        })
+    }
+
+    pub fn num_class(&self) -> usize {
+        self.num_class
+    }
+
+    pub fn num_feature(&self) -> usize {
+        self.num_feature
+    }
+
+    /// Raw per-class margins. XGBoost compares in f32, so rows are f32 too.
+    pub fn margins(&self, row: &[f32]) -> Vec<f32> {
+        let mut out = self.base_score.clone();
+
+        for (tree, class) in self.trees.iter().zip(&self.tree_class) {
+            out[*class] += tree.leaf(row);
+        }
+
+        out
+    }
+
+    pub fn predict_proba(&self, row: &[f32]) -> Vec<f64> {
+        let margins = self.margins(row);
+        let max = margins.iter().cloned().fold(f32::NEG_INFINITY, f32::max) as f64;
+
+        let exp: Vec<f64> = margins.iter().map(|m| (*m as f64 - max).exp()).collect();
+        let sum: f64 = exp.iter().sum();
+
+        exp.into_iter().map(|e| e / sum).collect()
+    }
+}
+
+// ---------------------------------------------------------------------
+// CLASSIFIER (scaler + booster + label encoder)
+// ---------------------------------------------------------------------
+#[derive(Deserialize)]
+struct LabelEncoderJson {
+    classes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XgbVerdict {
+    pub label: String,
+    pub probability: f64,
+    pub is_anomaly: bool,
+}
+
+impl From<&XgbVerdict> for AnomalyDetection {
+    fn from(verdict: &XgbVerdict) -> Self {
+        Self {
+            is_anomalous: verdict.is_anomaly,
+            anomaly_type: Some(verdict.label.to_uppercase()),
+        }
+    }
+}
+
+pub struct XgbModel {
+    booster: Booster,
+    scaler: Option<StandardScaler>,
+    classes: Vec<String>,
+}
+
+impl XgbModel {
+    /// Loads `sentinel_xgboost_multiclass.json`, `label_encoder.json` and, when
+    /// present, `scaler.json` from the `models/XGBoost` directory.
+    /// The booster JSON isn't bundled, as the pickled model it is exported
+    /// from never shipped: save it with `booster.save_model(...)` where the
+    /// model was trained, or run `export_models.py` next to the `.pkl`.
+    /// Until then only the LSTM scores flows.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let booster = Booster::load(&dir.join("sentinel_xgboost_multiclass.json"))?;
+
+        if booster.num_feature() != XGB_FEATURE_COUNT {
+            return Err(format!(
+                "XGBoost model expects {} features, pipeline produces {XGB_FEATURE_COUNT}",
+                booster.num_feature()
+            ));
+        }
+
+        let encoder_path = dir.join("label_encoder.json");
+        let encoder: LabelEncoderJson = fs::read_to_string(&encoder_path)
+            .map_err(|e| e.to_string())
+            .and_then(|raw| serde_json::from_str(&raw).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Failed to load {}: {e}", encoder_path.display()))?;
+
+        if encoder.classes.len() != booster.num_class() {
+            return Err(format!(
+                "Label encoder has {} classes, model has {}",
+                encoder.classes.len(),
+                booster.num_class()
+            ));
+        }
+
+        let scaler_path = dir.join("scaler.json");
+        let scaler = if scaler_path.exists() {
+            let scaler = StandardScaler::load(&scaler_path)?;
+            scaler.expect_features(&xgb_feature_names())?;
+            Some(scaler)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            booster,
+            scaler,
+            classes: encoder.classes,
+        })
+    }
+
+    pub fn classify(&self, features: &[f64; XGB_FEATURE_COUNT]) -> XgbVerdict {
+        let scaled = match &self.scaler {
+            Some(scaler) => scaler.transform(features),
+            None => features.to_vec(),
+        };
+
+        let row: Vec<f32> = scaled.iter().map(|v| *v as f32).collect();
+        self.classify_row(&row)
+    }
+
+    fn classify_row(&self, row: &[f32]) -> XgbVerdict {
+        let proba = self.booster.predict_proba(row);
+
+        // First maximum wins, like numpy's argmax
+        let (class, probability) = proba
+            .iter()
+            .enumerate()
+            .fold((0, f64::NEG_INFINITY), |best, (i, p)| if *p > best.1 { (i, *p) } else { best });
+
+        let label = self.classes[class].clone();
+
+        XgbVerdict {
+            is_anomaly: label.to_lowercase() != "benign",
+            label,
+            probability,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two classes over two features, one tree each:
+    //   class 0: f0 < 0.5 ? 1.0 : -1.0, missing goes left
+    //   class 1: f1 < 2.0 ? -0.5 : 0.75, missing goes right
+    fn model_json(base_score: &str) -> String {
+        format!(
+            r#"{{
+              "learner": {{
+                "learner_model_param": {{
+                  "base_score": "{base_score}", "num_class": "2", "num_feature": "2"
+                }},
+                "objective": {{ "name": "multi:softprob" }},
+                "gradient_booster": {{
+                  "name": "gbtree",
+                  "model": {{
+                    "tree_info": [0, 1],
+                    "trees": [
+                      {{
+                        "left_children": [1, -1, -1],
+                        "right_children": [2, -1, -1],
+                        "split_indices": [0, 0, 0],
+                        "split_conditions": [0.5, 1.0, -1.0],
+                        "default_left": [true, false, false]
+                      }},
+                      {{
+                        "left_children": [1, -1, -1],
+                        "right_children": [2, -1, -1],
+                        "split_indices": [1, 0, 0],
+                        "split_conditions": [2.0, -0.5, 0.75],
+                        "default_left": [0, 0, 0],
+                        "split_type": [0, 0, 0]
+                      }}
+                    ]
+                  }}
+                }}
+              }}
+            }}"#
+        )
+    }
+
+    fn booster(base_score: &str) -> Booster {
+        Booster::from_json(&model_json(base_score)).unwrap()
+    }
+
+    fn model(base_score: &str) -> XgbModel {
+        XgbModel {
+            booster: booster(base_score),
+            scaler: None,
+            classes: vec!["BENIGN".into(), "DoS".into()],
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn splits_left_below_the_threshold_and_right_from_it() {
+        let booster = booster("0E0");
+
+        assert_eq!(booster.margins(&[0.0, 1.0]), vec![1.0, -0.5]);
+        assert_eq!(booster.margins(&[0.49, 1.99]), vec![1.0, -0.5]);
+        // Equal to the threshold is not below it
+        assert_eq!(booster.margins(&[0.5, 2.0]), vec![-1.0, 0.75]);
+    }
+
+    #[test]
+    fn missing_values_follow_the_default_branch() {
+        let booster = booster("0E0");
+
+        assert_eq!(booster.margins(&[f32::NAN, f32::NAN]), vec![1.0, 0.75]);
+        assert_eq!(booster.margins(&[f32::NAN, 0.0]), vec![1.0, -0.5]);
+    }
+
+    #[test]
+    fn base_score_is_added_to_every_class() {
+        // 1.x writes a scalar, 2.x a bracketed list, optionally per class
+        assert_eq!(booster("5E-1").margins(&[0.0, 1.0]), vec![1.5, 0.0]);
+        assert_eq!(booster("[5E-1]").margins(&[0.0, 1.0]), vec![1.5, 0.0]);
+        assert_eq!(booster("[2.5E-1,1E0]").margins(&[0.0, 1.0]), vec![1.25, 0.5]);
+
+        assert!(Booster::from_json(&model_json("[1E0,2E0,3E0]")).is_err());
+    }
+
+    #[test]
+    fn softmax_picks_the_most_probable_class() {
+        let model = model("5E-1");
+
+        // Margins 1.5 and 0.0
+        let benign = model.classify_row(&[0.0, 1.0]);
+        assert_eq!(benign.label, "BENIGN");
+        assert!(!benign.is_anomaly);
+        assert_close(benign.probability, 1.0 / (1.0 + (-1.5f64).exp()));
+
+        // Margins -0.5 and 1.25
+        let dos = model.classify_row(&[0.5, 2.0]);
+        assert_eq!(dos.label, "DoS");
+        assert!(dos.is_anomaly);
+        assert_close(dos.probability, 1.0 / (1.0 + (-1.75f64).exp()));
+
+        let proba = model.booster.predict_proba(&[0.5, 2.0]);
+        assert_close(proba.iter().sum(), 1.0);
+    }
+
+    #[test]
+    fn ties_go_to_the_first_class() {
+        // Margins 0.75 and 0.75
+        let verdict = model("[-2.5E-1,1.25E0]").classify_row(&[0.0, 1.0]);
+
+        assert_eq!(verdict.label, "BENIGN");
+        assert_close(verdict.probability, 0.5);
+    }
+
+    fn tree(left: &[i32], right: &[i32]) -> Result<Tree, String> {
+        let n = left.len();
+        let tree: TreeJson = serde_json::from_value(serde_json::json!({
+            "left_children": left,
+            "right_children": right,
+            "split_indices": vec![0; n],
+            "split_conditions": vec![0.0; n],
+            "default_left": vec![false; n],
+        }))
+        .unwrap();
+
+        Tree::from_json(tree, 1)
+    }
+
+    #[test]
+    fn only_trees_are_accepted() {
+        assert!(tree(&[1, -1, -1], &[2, -1, -1]).is_ok());
+
+        // A child pointing back at the root, at itself or at an ancestor
+        assert!(tree(&[1, -1, -1], &[0, -1, -1]).is_err());
+        assert!(tree(&[1, 1, -1, -1], &[2, 3, -1, -1]).is_err());
+        assert!(tree(&[1, 3, -1, 1, -1, -1], &[2, 4, -1, 5, -1, -1]).is_err());
+        // Two parents sharing children, and both branches on one node
+        assert!(tree(&[1, 3, 3, -1, -1], &[2, 4, 4, -1, -1]).is_err());
+        assert!(tree(&[1, -1], &[1, -1]).is_err());
+        // No nodes, or a branch past the end
+        assert!(tree(&[], &[]).is_err());
+        assert!(tree(&[1, -1], &[5, -1]).is_err());
+    }
+}
@@ -0,0 +1,65 @@
+#!/usr/bin/env python3
+"""
+Converts the joblib/numpy model artifacts into the JSON files the Rust
+backend loads, so the app never has to unpickle anything at runtime.
+
+    python export_models.py [models_dir]
+"""
+
+import sys
+import json
+from pathlib import Path
+
+import joblib
+
+
+def export_scaler(src, dst):
+    scaler = joblib.load(src)
+
+    out = {
+        "mean": scaler.mean_.tolist(),
+        "scale": scaler.scale_.tolist(),
+    }
+
+    # Only present when the scaler was fitted on a DataFrame
+    names = getattr(scaler, "feature_names_in_", None)
+    if names is not None:
+        out["feature_names"] = [str(n) for n in names]
+
+    dst.write_text(json.dumps(out, indent=2))
+    print(f"{src.name} -> {dst.name}")
+
+
+def export_label_encoder(src, dst):
+    encoder = joblib.load(src)
+
+    dst.write_text(json.dumps({"classes": [str(c) for c in encoder.classes_]}, indent=2))
+    print(f"{src.name} -> {dst.name}")
+
+
+def export_xgb(src, dst):
+    model = joblib.load(src)
+
+    # XGBClassifier wraps a Booster; a bare Booster saves directly
+    booster = model.get_booster() if hasattr(model, "get_booster") else model
+    booster.save_model(str(dst))
+    print(f"{src.name} -> {dst.name}")
+
+
+def main():
+    base = Path(sys.argv[1]) if len(sys.argv) > 1 else Path(__file__).resolve().parent.parent / "models"
+
+    # ---------- LSTM ----------
+    export_scaler(base/"LSTM"/"lstm_scaler.pkl", base/"LSTM"/"lstm_scaler.json")
+
+    # ---------- XGB ----------
+    xgb_dir = base/"XGBoost"
+    export_scaler(xgb_dir/"scaler.pkl", xgb_dir/"scaler.json")
+    export_label_encoder(xgb_dir/"label_encoder.pkl", xgb_dir/"label_encoder.json")
+
+    if (xgb_dir/"sentinel_xgboost_multiclass.pkl").exists():
+        export_xgb(xgb_dir/"sentinel_xgboost_multiclass.pkl", xgb_dir/"sentinel_xgboost_multiclass.json")
+
+
+if __name__ == "__main__":
+    main()
@@ -1,7 +1,16 @@
-from scapy.all import get_if_list, get_if_addr
+from scapy.all import conf, get_if_list, get_if_addr, get_if_hwaddr
 import psutil
 import json
 
+
+def scapy_iface(iface):
+    # Windows capture names (\Device\NPF_{...}) only resolve through scapy's table
+    try:
+        return conf.ifaces.dev_from_networkname(iface)
+    except Exception:
+        return None
+
+
 def list_ifaces():
     out = []
 
@@ -9,20 +18,41 @@ def list_ifaces():
 
     for iface in get_if_list():
         ipv4 = None
+        ipv6 = []
+        mac = None
 
         try:
             ipv4 = get_if_addr(iface)
         except Exception:
             ipv4 = None
 
+        try:
+            mac = get_if_hwaddr(iface)
+        except Exception:
+            mac = None
+
+        dev = scapy_iface(iface)
+        if dev is not None:
+            mac = getattr(dev, "mac", None) or mac
+            ipv6 = list(getattr(dev, "ips", {}).get(6, []))
+
         if iface in addrs:
             for a in addrs[iface]:
                 if a.family == psutil.AF_INET:
                     ipv4 = a.address
+                elif a.family == psutil.AF_INET6:
+                    ipv6.append(a.address.split("%")[0])
+                elif a.family == psutil.AF_LINK:
+                    mac = a.address
+
+        if ipv4 == "0.0.0.0":
+            ipv4 = None
 
         out.append({
             "name": iface,
             "ipv4": ipv4,
+            "ipv6": sorted(set(ipv6)),
+            "mac": mac,
         })
 
     return out
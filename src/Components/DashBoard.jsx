@@ -1,4 +1,4 @@
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 
@@ -9,13 +9,35 @@ import ThreatSummary from "./ThreatSummary";
 import NetworkCard from "./NetworkCard";
 import RecentActivity from "./RecentActivity";
 
+// Applies one network_delta to the current list
+const applyDelta = (networks, delta) => {
+  const byId = new Map(networks.map((n) => [n.id, n]));
+
+  for (const change of delta.changes) {
+    if (change.op === "remove") byId.delete(change.id);
+    else byId.set(change.network.id, change.network);
+  }
+
+  const order = delta.order ?? networks.map((n) => n.id);
+  const ordered = order.filter((id) => byId.has(id)).map((id) => byId.get(id));
+  // Added interfaces missing from an unchanged order go last
+  for (const [id, network] of byId) {
+    if (!order.includes(id)) ordered.push(network);
+  }
+  return ordered;
+};
+
 const Dashboard = () => {
   const [networks, setNetworks] = useState([]);
   const [isScanning, setIsScanning] = useState(false);
+  // Sequence number of the last applied delta
+  const seqRef = useRef(0);
 
   const initializeNetworks = async () => {
     try {
-      setNetworks(await invoke("get_networks"));
+      const snapshot = await invoke("get_snapshot");
+      seqRef.current = snapshot.seq;
+      setNetworks(snapshot.networks);
     } catch (error) {
       console.error("Failed to initialize networks:", error);
     }
@@ -32,9 +54,18 @@ const Dashboard = () => {
   useEffect(() => {
     initializeNetworks();
 
-    const unlistenPromise = listen("network_update", (event) => {
-      console.log("🔁 Full network update:", event.payload);
-      setNetworks(event.payload);
+    const unlistenPromise = listen("network_delta", (event) => {
+      const delta = event.payload;
+
+      if (delta.seq <= seqRef.current) return;
+      if (delta.seq !== seqRef.current + 1) {
+        // Missed a delta, start over from a snapshot
+        initializeNetworks();
+        return;
+      }
+
+      seqRef.current = delta.seq;
+      setNetworks((current) => applyDelta(current, delta));
     });
 
     return () => {
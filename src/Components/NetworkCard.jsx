@@ -1,4 +1,5 @@
 import React from "react";
+import { invoke } from "@tauri-apps/api/core";
 import {
   Wifi,
   Server,
@@ -12,8 +13,26 @@ import {
   CheckCircle,
   AlertTriangle,
   ShieldAlert,
+  Play,
+  Pause,
+  Square,
 } from "lucide-react";
 
+// Actions offered for each monitoringState, as [command, label, icon]
+const MONITOR_ACTIONS = {
+  monitoring: [
+    ["pause_interface", "Pause", Pause],
+    ["stop_interface", "Stop", Square],
+  ],
+  paused: [
+    ["resume_interface", "Resume", Play],
+    ["stop_interface", "Stop", Square],
+  ],
+  stopped: [["start_interface", "Start", Play]],
+  excluded: [["start_interface", "Start", Play]],
+  unavailable: [],
+};
+
 const NetworkCard = ({ network }) => {
   if (!network) return null;
 
@@ -21,6 +40,7 @@ const NetworkCard = ({ network }) => {
     id,
     name,
     status,
+    monitoringState,
     anomaly,
     ipInfo,
     bandwidth,
@@ -31,6 +51,9 @@ const NetworkCard = ({ network }) => {
     activePorts,
   } = network;
 
+  const runAction = (command) =>
+    invoke(command, { id }).catch((e) => console.error(e));
+
   const formatBytes = (bytes) => {
     if (!bytes || bytes <= 0) return "0 B";
     const units = ["B", "KB", "MB", "GB"];
@@ -120,6 +143,25 @@ const NetworkCard = ({ network }) => {
           </div>
         </div>
 
+        <div className="flex justify-between items-center text-sm">
+          <span className="text-gray-600 dark:text-gray-400 capitalize">
+            Monitoring: {monitoringState ?? "unavailable"}
+          </span>
+          <div className="flex gap-2">
+            {(MONITOR_ACTIONS[monitoringState] ?? []).map(
+              ([command, label, Icon]) => (
+                <button
+                  key={command}
+                  onClick={() => runAction(command)}
+                  className="flex items-center gap-1 px-2 py-1 rounded-lg bg-gray-100 dark:bg-gray-800 text-gray-700 dark:text-gray-300 hover:bg-gray-200 dark:hover:bg-gray-700"
+                >
+                  <Icon size={14} /> {label}
+                </button>
+              )
+            )}
+          </div>
+        </div>
+
         <div>
           <h3 className="text-sm font-semibold text-gray-700 dark:text-gray-300 mb-2 flex items-center gap-2">
             <Network size={16} /> Network Info
@@ -127,13 +169,13 @@ const NetworkCard = ({ network }) => {
           <div className="grid grid-cols-2 gap-y-1 text-sm text-gray-700 dark:text-gray-400">
             <p
               className="overflow-hidden text-ellipsis whitespace-nowrap"
-              title={ipInfo?.ipv4 || "N/A"}
+              title={ipInfo?.ipv4Addrs?.join("\n") || "N/A"}
             >
               IPv4: {ipInfo?.ipv4 ?? "N/A"}
             </p>
             <p
               className="overflow-hidden text-ellipsis whitespace-nowrap"
-              title={ipInfo?.ipv6 || "N/A"}
+              title={ipInfo?.ipv6Addrs?.join("\n") || "N/A"}
             >
               IPv6: {ipInfo?.ipv6 ?? "N/A"}
             </p>
@@ -149,12 +191,14 @@ const NetworkCard = ({ network }) => {
           <div className="grid grid-cols-2 text-sm gap-y-1">
             <p className="flex items-center gap-1 text-green-600 dark:text-green-400 font-medium">
               <Download size={14} /> RX:{" "}
-              {bandwidth?.rx_rate_mbps?.toFixed(2) ?? "0.00"} Mbps
+              {bandwidth?.downloadAvg?.toFixed(2) ?? "0.00"} Mbps
             </p>
             <p className="flex items-center gap-1 text-blue-600 dark:text-blue-400 font-medium">
               <Upload size={14} /> TX:{" "}
-              {bandwidth?.tx_rate_mbps?.toFixed(2) ?? "0.00"} Mbps
+              {bandwidth?.uploadAvg?.toFixed(2) ?? "0.00"} Mbps
             </p>
+            <p>Peak RX: {bandwidth?.peakDownload?.toFixed(2) ?? "0.00"} Mbps</p>
+            <p>Peak TX: {bandwidth?.peakUpload?.toFixed(2) ?? "0.00"} Mbps</p>
             <p>Received: {formatBytes(receivedBytes)}</p>
             <p>Transmitted: {formatBytes(transmittedBytes)}</p>
           </div>